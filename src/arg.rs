@@ -1,11 +1,33 @@
 use std::fmt::{Debug, Display, Formatter};
 use ansi_term::Style;
 use std::error::Error;
-use crate::display::canvas::SampleMode;
+use crate::display::canvas::{BlendMode, ColorSpace, SampleMode, TextureWrap, WrapMode, RGBA8};
+use crate::display::fog::{Fog, FogMode};
+use crate::display::light::Light;
+use crate::display::wireframe::RenderMode;
+use crate::display::antialias::AntiAliasing;
+use crate::display::procedural::Procedural;
+use crate::math::vector::Vector3D;
+use crate::model::platonic::CubeLayout;
+
+// Model files that can be given in place of a built-in object
+const MODEL_EXTENSIONS: [&str; 5] = [ ".vox", ".stl", ".ply", ".gltf", ".glb" ];
+const OBJECT_ISSUE: &str = "Not one of cube, tetrahedron, octahedron, dodecahedron, icosahedron, uv_sphere, torus, cylinder, cone, plane, capsule, terrain or a .vox, .stl, .ply, .gltf or .glb file";
 
 pub enum ArgObject {
     Cube,
-    UvSphere
+    Tetrahedron,
+    Octahedron,
+    Dodecahedron,
+    Icosahedron,
+    UvSphere,
+    Torus,
+    Cylinder,
+    Cone,
+    Plane,
+    Capsule,
+    Terrain,
+    File(String) // model file, loaded by its extension
 }
 
 enum ArgSelector {
@@ -15,17 +37,60 @@ enum ArgSelector {
     Resolution,
     Interpolation,
     LatitudeSteps,
-    LongitudeSteps
+    LongitudeSteps,
+    Light,
+    Shadows,
+    Ground,
+    Fog,
+    FogColor,
+    Environment,
+    Wrap,
+    BorderColor,
+    Blend,
+    ColorSpace,
+    TextureFps,
+    Render,
+    WireColor,
+    AntiAliasing,
+    CubeLayout,
+    Heightmap,
+    HeightScale,
+    TerrainGrid,
+    Export,
+    ExportBake,
+    Orbit
 }
 
 pub struct Args {
     pub object: Option<ArgObject>,
     pub texture: Option<String>,
+    pub procedural: Option<Procedural>,
     pub output: Option<String>,
     pub resolution: u16,
     pub interpolation: SampleMode,
     pub lat: u16,
     pub lng: u16,
+    pub light: Option<Light>,
+    pub shadows: Option<u32>,
+    pub ground: Option<f32>,
+    pub fog: Option<Fog>,
+    pub fog_color: Option<RGBA8>,
+    pub environment: Option<String>,
+    pub wrap: Option<TextureWrap>,
+    pub border_color: RGBA8,
+    pub blend: Option<BlendMode>,
+    pub color_space: ColorSpace,
+    pub texture_fps: u16,
+    pub render_mode: RenderMode,
+    pub wire_color: RGBA8,
+    pub aa: AntiAliasing,
+    pub cube_layout: CubeLayout,
+    pub heightmap: Option<String>,
+    pub height_scale: f32,
+    pub terrain_grid: (u16, u16),
+    pub export: Option<String>,
+    pub export_bake: bool,
+    pub orbit: Option<ArgObject>,
 
     active_selector: ArgSelector
 }
@@ -35,11 +100,33 @@ impl Args {
         Self {
             object: None,
             texture: None,
+            procedural: None,
             output: None,
             resolution: 256u16,
             interpolation: SampleMode::BiLinear,
             lat: 128u16,
             lng: 64u16,
+            light: None,
+            shadows: None,
+            ground: None,
+            fog: None,
+            fog_color: None,
+            environment: None,
+            wrap: None,
+            border_color: RGBA8 { r: 0u8, g: 0u8, b: 0u8, a: 0u8 },
+            blend: None,
+            color_space: ColorSpace::Gamma,
+            texture_fps: 24u16,
+            render_mode: RenderMode::Shaded,
+            wire_color: RGBA8::white(),
+            aa: AntiAliasing::Off,
+            cube_layout: CubeLayout::Same,
+            heightmap: None,
+            height_scale: 0.5f32,
+            terrain_grid: (64u16, 64u16),
+            export: None,
+            export_bake: false,
+            orbit: None,
             active_selector: ArgSelector::Unset
         }
     }
@@ -49,17 +136,20 @@ impl Args {
             if self.object.is_none() {
                 return Err(ArgError { name: String::from("object"), issue: String::from("Argument is required") });
             }
+            let terrain: bool = matches!(self.object, Some(ArgObject::Terrain)) || matches!(self.orbit, Some(ArgObject::Terrain));
+            if terrain && self.heightmap.is_none() {
+                return Err(ArgError { name: String::from("heightmap"), issue: String::from("Argument is required for terrain") });
+            }
+            // Without baking, the orbiting object would be written over the first one
+            if self.export.is_some() && !self.export_bake && self.orbit.is_some() {
+                return Err(ArgError { name: String::from("orbit"), issue: String::from("Can only be exported with --export-bake") });
+            }
             return Ok(());
         }
         if index == 1 {
-            let ob_str = &args[index];
-
-            if ob_str.eq_ignore_ascii_case("cube") {
-                self.object = Some(ArgObject::Cube);
-            } else if ob_str.eq_ignore_ascii_case("uv_sphere") || ob_str.eq_ignore_ascii_case("uv") {
-                self.object = Some(ArgObject::UvSphere);
-            } else {
-                return Err(ArgError { name: String::from("object"), issue: String::from("Not one of cube, uv_sphere") });
+            match parse_object(&args[index]) {
+                Some(object) => self.object = Some(object),
+                None => return Err(ArgError { name: String::from("object"), issue: String::from(OBJECT_ISSUE) })
             }
         } else {
             if matches!(self.active_selector, ArgSelector::Unset) {
@@ -86,6 +176,48 @@ impl Args {
             self.active_selector = ArgSelector::LatitudeSteps;
         } else if selector.eq_ignore_ascii_case("--lng") {
             self.active_selector = ArgSelector::LongitudeSteps;
+        } else if selector.eq_ignore_ascii_case("--light") || selector.eq_ignore_ascii_case("-l") {
+            self.active_selector = ArgSelector::Light;
+        } else if selector.eq_ignore_ascii_case("--shadows") {
+            self.active_selector = ArgSelector::Shadows;
+        } else if selector.eq_ignore_ascii_case("--ground") {
+            self.active_selector = ArgSelector::Ground;
+        } else if selector.eq_ignore_ascii_case("--fog") {
+            self.active_selector = ArgSelector::Fog;
+        } else if selector.eq_ignore_ascii_case("--fog-color") {
+            self.active_selector = ArgSelector::FogColor;
+        } else if selector.eq_ignore_ascii_case("--environment") || selector.eq_ignore_ascii_case("-e") {
+            self.active_selector = ArgSelector::Environment;
+        } else if selector.eq_ignore_ascii_case("--wrap") || selector.eq_ignore_ascii_case("-w") {
+            self.active_selector = ArgSelector::Wrap;
+        } else if selector.eq_ignore_ascii_case("--border-color") {
+            self.active_selector = ArgSelector::BorderColor;
+        } else if selector.eq_ignore_ascii_case("--blend") || selector.eq_ignore_ascii_case("-b") {
+            self.active_selector = ArgSelector::Blend;
+        } else if selector.eq_ignore_ascii_case("--color-space") {
+            self.active_selector = ArgSelector::ColorSpace;
+        } else if selector.eq_ignore_ascii_case("--texture-fps") {
+            self.active_selector = ArgSelector::TextureFps;
+        } else if selector.eq_ignore_ascii_case("--render") {
+            self.active_selector = ArgSelector::Render;
+        } else if selector.eq_ignore_ascii_case("--wire-color") {
+            self.active_selector = ArgSelector::WireColor;
+        } else if selector.eq_ignore_ascii_case("--aa") {
+            self.active_selector = ArgSelector::AntiAliasing;
+        } else if selector.eq_ignore_ascii_case("--cube-layout") {
+            self.active_selector = ArgSelector::CubeLayout;
+        } else if selector.eq_ignore_ascii_case("--heightmap") {
+            self.active_selector = ArgSelector::Heightmap;
+        } else if selector.eq_ignore_ascii_case("--height-scale") {
+            self.active_selector = ArgSelector::HeightScale;
+        } else if selector.eq_ignore_ascii_case("--terrain-grid") {
+            self.active_selector = ArgSelector::TerrainGrid;
+        } else if selector.eq_ignore_ascii_case("--export") {
+            self.active_selector = ArgSelector::Export;
+        } else if selector.eq_ignore_ascii_case("--export-bake") {
+            self.active_selector = ArgSelector::ExportBake;
+        } else if selector.eq_ignore_ascii_case("--orbit") {
+            self.active_selector = ArgSelector::Orbit;
         } else {
             return Err(ArgError { name: String::from("selector"), issue: format!("Unrecognized selector {}", selector) });
         }
@@ -95,7 +227,13 @@ impl Args {
     fn digest_value(&mut self, value: &String) -> Result<(), ArgError> {
         match self.active_selector {
             ArgSelector::Texture => {
-                self.texture = Some(value.clone());
+                if value.starts_with("proc:") {
+                    self.procedural = Some(parse_procedural(value)?);
+                    self.texture = None;
+                } else {
+                    self.texture = Some(value.clone());
+                    self.procedural = None;
+                }
             },
             ArgSelector::Output => {
                 self.output = Some(value.clone());
@@ -114,6 +252,12 @@ impl Args {
                     self.interpolation = SampleMode::BiLinear;
                 } else if value.eq_ignore_ascii_case("bicubic") {
                     self.interpolation = SampleMode::BiCubic;
+                } else if value.eq_ignore_ascii_case("mip") || value.eq_ignore_ascii_case("mip_nearest") {
+                    self.interpolation = SampleMode::MipNearest;
+                } else if value.eq_ignore_ascii_case("trilinear") {
+                    self.interpolation = SampleMode::Trilinear;
+                } else if value.eq_ignore_ascii_case("anisotropic") || value.eq_ignore_ascii_case("aniso") {
+                    self.interpolation = SampleMode::Anisotropic;
                 } else {
                     return Err(ArgError { name: String::from("interpolation"), issue: format!("Unrecognized sample mode ({})", value) });
                 }
@@ -132,6 +276,186 @@ impl Args {
                 }
                 self.lng = parse.unwrap();
             },
+            ArgSelector::Light => {
+                let split = value.split_once(':');
+                let vector = split.and_then(| (_, v) | parse_vector(v));
+                if vector.is_none() {
+                    return Err(ArgError { name: String::from("light"), issue: format!("Invalid light ({})", value) });
+                }
+                let kind: &str = split.unwrap().0;
+                if kind.eq_ignore_ascii_case("directional") || kind.eq_ignore_ascii_case("dir") {
+                    self.light = Some(Light::directional(&vector.unwrap()));
+                } else if kind.eq_ignore_ascii_case("point") {
+                    self.light = Some(Light::point(&vector.unwrap()));
+                } else {
+                    return Err(ArgError { name: String::from("light"), issue: format!("Unrecognized light type ({})", kind) });
+                }
+            },
+            ArgSelector::Shadows => {
+                if value.eq_ignore_ascii_case("off") {
+                    self.shadows = None;
+                } else if value.eq_ignore_ascii_case("hard") {
+                    self.shadows = Some(0u32);
+                } else if value.eq_ignore_ascii_case("soft") {
+                    self.shadows = Some(2u32);
+                } else {
+                    return Err(ArgError { name: String::from("shadows"), issue: format!("Unrecognized shadow mode ({})", value) });
+                }
+            },
+            ArgSelector::Ground => {
+                let parse = value.parse::<f32>();
+                if parse.is_err() {
+                    return Err(ArgError { name: String::from("ground"), issue: format!("Invalid ground offset ({})", value) });
+                }
+                self.ground = Some(parse.unwrap());
+            },
+            ArgSelector::Fog => {
+                let parts: Vec<&str> = value.split(':').collect();
+                if parts.len() == 1usize && parts[0].eq_ignore_ascii_case("off") {
+                    self.fog = None;
+                } else {
+                    let mode: FogMode = match parts[0].to_ascii_lowercase().as_str() {
+                        "linear" => FogMode::Linear,
+                        "exp" => FogMode::Exponential,
+                        "exp2" => FogMode::ExponentialSquared,
+                        _ => return Err(ArgError { name: String::from("fog"), issue: format!("Unrecognized fog mode ({})", parts[0]) })
+                    };
+                    let start = parts.get(1).and_then(| p | p.parse::<f32>().ok());
+                    let end = parts.get(2).and_then(| p | p.parse::<f32>().ok());
+                    if parts.len() != 3usize || start.is_none() || end.is_none() {
+                        return Err(ArgError { name: String::from("fog"), issue: format!("Expected mode:start:end ({})", value) });
+                    }
+                    self.fog = Some(Fog::new(mode, start.unwrap(), end.unwrap()));
+                }
+            },
+            ArgSelector::FogColor => {
+                let color = parse_color(value);
+                if color.is_none() {
+                    return Err(ArgError { name: String::from("fog-color"), issue: format!("Invalid color ({})", value) });
+                }
+                self.fog_color = color;
+            },
+            ArgSelector::Environment => {
+                self.environment = Some(value.clone());
+            },
+            ArgSelector::Wrap => {
+                let (u_str, v_str) = value.split_once(',').unwrap_or((value.as_str(), value.as_str()));
+                let u = parse_wrap_mode(u_str);
+                let v = parse_wrap_mode(v_str);
+                if u.is_none() || v.is_none() {
+                    return Err(ArgError { name: String::from("wrap"), issue: format!("Unrecognized wrap mode ({})", value) });
+                }
+                self.wrap = Some(TextureWrap::new(u.unwrap(), v.unwrap()));
+            },
+            ArgSelector::BorderColor => {
+                let color = parse_color(value);
+                if color.is_none() {
+                    return Err(ArgError { name: String::from("border-color"), issue: format!("Invalid color ({})", value) });
+                }
+                self.border_color = color.unwrap();
+            },
+            ArgSelector::Blend => {
+                let (mode, param) = value.split_once(':').unwrap_or((value.as_str(), ""));
+                self.blend = Some(match mode.to_ascii_lowercase().as_str() {
+                    "replace" => BlendMode::Replace,
+                    "alpha_test" | "test" => {
+                        let threshold = if param.is_empty() { Ok(128u8) } else { param.parse::<u8>() };
+                        if threshold.is_err() {
+                            return Err(ArgError { name: String::from("blend"), issue: format!("Invalid alpha test threshold ({})", param) });
+                        }
+                        BlendMode::AlphaTest(threshold.unwrap())
+                    },
+                    "over" | "alpha" => BlendMode::AlphaOver,
+                    "add" | "additive" => BlendMode::Additive,
+                    "multiply" => BlendMode::Multiply,
+                    "screen" => BlendMode::Screen,
+                    _ => return Err(ArgError { name: String::from("blend"), issue: format!("Unrecognized blend mode ({})", value) })
+                });
+            },
+            ArgSelector::ColorSpace => {
+                self.color_space = match value.to_ascii_lowercase().as_str() {
+                    "linear" => ColorSpace::Linear,
+                    "gamma" | "srgb" => ColorSpace::Gamma,
+                    _ => return Err(ArgError { name: String::from("color-space"), issue: format!("Unrecognized color space ({})", value) })
+                };
+            },
+            ArgSelector::TextureFps => {
+                let parse = value.parse::<u16>();
+                if parse.is_err() || parse.as_ref().is_ok_and(| fps | *fps == 0u16) {
+                    return Err(ArgError { name: String::from("texture-fps"), issue: format!("Invalid frame rate ({})", value) });
+                }
+                self.texture_fps = parse.unwrap();
+            },
+            ArgSelector::Render => {
+                self.render_mode = match value.to_ascii_lowercase().as_str() {
+                    "shaded" => RenderMode::Shaded,
+                    "wireframe" | "wire" => RenderMode::Wireframe,
+                    "overlay" => RenderMode::ShadedWireframe,
+                    "hidden" | "hidden_line" => RenderMode::HiddenLine,
+                    _ => return Err(ArgError { name: String::from("render"), issue: format!("Unrecognized render mode ({})", value) })
+                };
+            },
+            ArgSelector::WireColor => {
+                let color = parse_color(value);
+                if color.is_none() {
+                    return Err(ArgError { name: String::from("wire-color"), issue: format!("Invalid color ({})", value) });
+                }
+                self.wire_color = color.unwrap();
+            },
+            ArgSelector::AntiAliasing => {
+                self.aa = match value.to_ascii_lowercase().as_str() {
+                    "off" => AntiAliasing::Off,
+                    // Named by the grid, since the factor applies along each axis
+                    "ssaa2x2" => AntiAliasing::Supersample(2u32),
+                    "ssaa4x4" => AntiAliasing::Supersample(4u32),
+                    "ssaa8x8" => AntiAliasing::Supersample(8u32),
+                    "msaa2" => AntiAliasing::Multisample(2u32),
+                    "msaa4" => AntiAliasing::Multisample(4u32),
+                    "msaa8" => AntiAliasing::Multisample(8u32),
+                    _ => return Err(ArgError { name: String::from("aa"), issue: format!("Unrecognized anti-aliasing mode ({})", value) })
+                };
+            },
+            ArgSelector::CubeLayout => {
+                self.cube_layout = match value.to_ascii_lowercase().as_str() {
+                    "same" => CubeLayout::Same,
+                    "cross" => CubeLayout::Cross,
+                    "3x2" => CubeLayout::Strip3x2,
+                    "6x1" => CubeLayout::Strip6x1,
+                    _ => return Err(ArgError { name: String::from("cube-layout"), issue: format!("Unrecognized cube layout ({})", value) })
+                };
+            },
+            ArgSelector::Heightmap => {
+                self.heightmap = Some(value.clone());
+            },
+            ArgSelector::HeightScale => {
+                let parse = value.parse::<f32>();
+                if parse.is_err() {
+                    return Err(ArgError { name: String::from("height-scale"), issue: format!("Invalid height scale ({})", value) });
+                }
+                self.height_scale = parse.unwrap();
+            },
+            ArgSelector::TerrainGrid => {
+                let steps: Vec<Result<u16, _>> = value.split(',').map(| part | part.trim().parse::<u16>()).collect();
+                self.terrain_grid = match steps.as_slice() {
+                    [ Ok(n) ] if *n > 0u16 => (*n, *n),
+                    [ Ok(cols), Ok(rows) ] if *cols > 0u16 && *rows > 0u16 => (*cols, *rows),
+                    _ => return Err(ArgError { name: String::from("terrain-grid"), issue: format!("Invalid grid resolution ({})", value) })
+                };
+            },
+            ArgSelector::Export | ArgSelector::ExportBake => {
+                let lower: String = value.to_ascii_lowercase();
+                if !lower.ends_with(".obj") && !lower.ends_with(".ply") {
+                    return Err(ArgError { name: String::from("export"), issue: format!("Not an .obj or .ply path ({})", value) });
+                }
+                self.export = Some(value.clone());
+                self.export_bake = matches!(self.active_selector, ArgSelector::ExportBake);
+            },
+            ArgSelector::Orbit => {
+                match parse_object(value) {
+                    Some(object) => self.orbit = Some(object),
+                    None => return Err(ArgError { name: String::from("orbit"), issue: String::from(OBJECT_ISSUE) })
+                }
+            },
             ArgSelector::Unset => panic!("Selector unset while digesting value!")
         }
         self.active_selector = ArgSelector::Unset;
@@ -139,6 +463,107 @@ impl Args {
     }
 }
 
+fn parse_object(value: &str) -> Option<ArgObject> {
+    let ret: ArgObject = if value.eq_ignore_ascii_case("cube") {
+        ArgObject::Cube
+    } else if value.eq_ignore_ascii_case("tetrahedron") || value.eq_ignore_ascii_case("d4") {
+        ArgObject::Tetrahedron
+    } else if value.eq_ignore_ascii_case("octahedron") || value.eq_ignore_ascii_case("d8") {
+        ArgObject::Octahedron
+    } else if value.eq_ignore_ascii_case("dodecahedron") || value.eq_ignore_ascii_case("d12") {
+        ArgObject::Dodecahedron
+    } else if value.eq_ignore_ascii_case("icosahedron") || value.eq_ignore_ascii_case("d20") {
+        ArgObject::Icosahedron
+    } else if value.eq_ignore_ascii_case("uv_sphere") || value.eq_ignore_ascii_case("uv") {
+        ArgObject::UvSphere
+    } else if value.eq_ignore_ascii_case("torus") {
+        ArgObject::Torus
+    } else if value.eq_ignore_ascii_case("cylinder") {
+        ArgObject::Cylinder
+    } else if value.eq_ignore_ascii_case("cone") {
+        ArgObject::Cone
+    } else if value.eq_ignore_ascii_case("plane") {
+        ArgObject::Plane
+    } else if value.eq_ignore_ascii_case("capsule") {
+        ArgObject::Capsule
+    } else if value.eq_ignore_ascii_case("terrain") {
+        ArgObject::Terrain
+    } else if MODEL_EXTENSIONS.iter().any(| ext | value.to_ascii_lowercase().ends_with(ext)) {
+        ArgObject::File(String::from(value))
+    } else {
+        return None;
+    };
+    Some(ret)
+}
+
+fn parse_vector(value: &str) -> Option<Vector3D> {
+    let mut components: [f32; 3] = [0f32; 3];
+    let mut count: usize = 0usize;
+    for part in value.split(',') {
+        if count >= 3usize { return None; }
+        components[count] = part.trim().parse::<f32>().ok()?;
+        count += 1usize;
+    }
+    if count != 3usize { return None; }
+    Some(Vector3D::new(components[0], components[1], components[2]))
+}
+
+fn parse_color(value: &str) -> Option<RGBA8> {
+    let parts: Vec<&str> = value.split(',').collect();
+    if parts.len() != 3usize && parts.len() != 4usize { return None; }
+    let mut channels: [u8; 4] = [255u8; 4];
+    for (idx, part) in parts.iter().enumerate() {
+        channels[idx] = part.trim().parse::<u8>().ok()?;
+    }
+    Some(RGBA8 { r: channels[0], g: channels[1], b: channels[2], a: channels[3] })
+}
+
+// The border color is substituted from --border-color when rendering
+fn parse_wrap_mode(value: &str) -> Option<WrapMode> {
+    return match value.trim().to_ascii_lowercase().as_str() {
+        "repeat" => Some(WrapMode::Repeat),
+        "mirror" => Some(WrapMode::MirroredRepeat),
+        "clamp" => Some(WrapMode::ClampToEdge),
+        "border" => Some(WrapMode::ClampToBorder),
+        _ => None
+    };
+}
+
+// Parses proc:<kind>[:<param>...], where colors are given as r,g,b[,a]
+fn parse_procedural(value: &str) -> Result<Procedural, ArgError> {
+    let parts: Vec<&str> = value.split(':').collect();
+    let invalid = || ArgError { name: String::from("texture"), issue: format!("Invalid procedural texture ({})", value) };
+    let number = | idx: usize, default: u32 | -> Result<u32, ArgError> {
+        match parts.get(idx) {
+            Some(p) => p.parse::<u32>().map_err(| _ | invalid()),
+            None => Ok(default)
+        }
+    };
+    let color = | idx: usize, default: RGBA8 | -> Result<RGBA8, ArgError> {
+        match parts.get(idx) {
+            Some(p) => parse_color(p).ok_or_else(invalid),
+            None => Ok(default)
+        }
+    };
+
+    let magenta: RGBA8 = RGBA8 { r: 255u8, g: 0u8, b: 255u8, a: 255u8 };
+    let kind: String = parts.get(1).map_or(String::new(), | k | k.to_ascii_lowercase());
+    let ret: Procedural = match kind.as_str() {
+        "checker" => Procedural::Checker { cells: number(2, 8)?.max(1u32), a: color(3, magenta)?, b: color(4, RGBA8::black())? },
+        "gradient" => Procedural::LinearGradient { a: color(2, RGBA8::black())?, b: color(3, RGBA8::white())? },
+        "radial" => Procedural::RadialGradient { inner: color(2, RGBA8::white())?, outer: color(3, RGBA8::black())? },
+        "uv" => Procedural::UvGrid { cells: number(2, 8)?.max(1u32) },
+        "noise" => Procedural::ValueNoise { scale: number(2, 8)?.max(1u32), seed: number(3, 0)? },
+        "perlin" => Procedural::PerlinNoise { scale: number(2, 8)?.max(1u32), seed: number(3, 0)? },
+        "stripes" => Procedural::Stripes { count: number(2, 8)?.max(1u32), a: color(3, RGBA8::white())?, b: color(4, RGBA8::black())? },
+        _ => return Err(ArgError {
+            name: String::from("texture"),
+            issue: format!("Not one of checker, gradient, radial, uv, noise, perlin, stripes ({})", kind)
+        })
+    };
+    Ok(ret)
+}
+
 pub struct ArgError {
     name: String,
     issue: String
@@ -1,3 +1,12 @@
 pub mod camera;
 pub mod canvas;
 pub mod render;
+pub mod light;
+pub mod shadow;
+pub mod fog;
+pub mod environment;
+pub mod procedural;
+pub mod animation;
+pub mod draw;
+pub mod wireframe;
+pub mod antialias;
@@ -0,0 +1,186 @@
+use std::fs::File;
+use std::path::Path;
+use gif::{ColorOutput, DecodeOptions, DisposalMethod, Frame};
+use image::{DynamicImage, ImageResult, RgbaImage};
+use crate::display::canvas::{BlendMode, Canvas, ColorSpace, ImageCanvas, ImageCanvasError, MipChain, PixelDraw, RGBA8};
+
+// GIFs commonly use a delay of 0 to mean "as fast as possible", which viewers show at 10 fps
+const GIF_DEFAULT_DELAY: f32 = 0.1f32;
+
+// A texture made of several frames, each shown for its own duration and looping forever
+pub struct AnimatedCanvas {
+    frames: Vec<ImageCanvas>,
+    delays: Vec<f32>,
+    duration: f32,
+    current: usize
+}
+
+impl AnimatedCanvas {
+
+    pub fn new(frames: Vec<ImageCanvas>, delays: Vec<f32>) -> Self {
+        assert!(!frames.is_empty(), "Animated canvas needs at least one frame");
+        assert_eq!(frames.len(), delays.len(), "Every frame needs a delay");
+        let duration: f32 = delays.iter().sum();
+        return Self { frames, delays, duration, current: 0usize };
+    }
+
+    pub fn from_gif<P>(path: P) -> Result<AnimatedCanvas, ImageCanvasError> where P: AsRef<Path> {
+        let file = File::open(path);
+        if file.is_err() {
+            return Err(ImageCanvasError::new(&format!("{}", file.err().unwrap())));
+        }
+        let mut options: DecodeOptions = DecodeOptions::new();
+        options.set_color_output(ColorOutput::RGBA);
+        let read = options.read_info(file.unwrap());
+        if read.is_err() {
+            return Err(ImageCanvasError::new(&format!("{}", read.err().unwrap())));
+        }
+        let mut decoder = read.unwrap();
+
+        // Frames only cover part of the logical screen, so they are composited in the order they come in
+        let mut screen: RgbaImage = RgbaImage::new(decoder.width() as u32, decoder.height() as u32);
+        let mut frames: Vec<ImageCanvas> = Vec::new();
+        let mut delays: Vec<f32> = Vec::new();
+        loop {
+            let next = decoder.read_next_frame();
+            if next.is_err() {
+                return Err(ImageCanvasError::new(&format!("{}", next.err().unwrap())));
+            }
+            let frame: &Frame = match next.unwrap() {
+                Some(frame) => frame,
+                None => break
+            };
+            let previous: Option<RgbaImage> = if frame.dispose == DisposalMethod::Previous { Some(screen.clone()) } else { None };
+
+            let left: u32 = frame.left as u32;
+            let top: u32 = frame.top as u32;
+            for y in 0 .. (frame.height as u32) {
+                for x in 0 .. (frame.width as u32) {
+                    let (sx, sy) = (left + x, top + y);
+                    if sx >= screen.width() || sy >= screen.height() { continue; }
+                    let i: usize = (((y * (frame.width as u32)) + x) * 4u32) as usize;
+                    // Transparent indices leave the previous frame showing through
+                    if frame.buffer[i + 3usize] == 0u8 { continue; }
+                    screen.put_pixel(sx, sy, image::Rgba([ frame.buffer[i], frame.buffer[i + 1usize], frame.buffer[i + 2usize], frame.buffer[i + 3usize] ]));
+                }
+            }
+
+            frames.push(ImageCanvas::new(DynamicImage::ImageRgba8(screen.clone())));
+            delays.push(if frame.delay == 0u16 { GIF_DEFAULT_DELAY } else { (frame.delay as f32) / 100f32 });
+
+            match frame.dispose {
+                DisposalMethod::Background => {
+                    for y in top .. (top + frame.height as u32).min(screen.height()) {
+                        for x in left .. (left + frame.width as u32).min(screen.width()) {
+                            screen.put_pixel(x, y, image::Rgba([ 0u8, 0u8, 0u8, 0u8 ]));
+                        }
+                    }
+                },
+                DisposalMethod::Previous => screen = previous.unwrap(),
+                _ => {}
+            }
+        }
+
+        if frames.is_empty() {
+            return Err(ImageCanvasError::new("GIF contains no frames"));
+        }
+        return Ok(AnimatedCanvas::new(frames, delays));
+    }
+
+    // Loads numbered images, where a run of '#' in the pattern stands for the zero-padded frame number
+    // (e.g. clouds_###.png). Numbering starts at 0 or 1 and ends at the first missing file.
+    pub fn from_sequence(pattern: &str, frame_time: f32) -> Result<AnimatedCanvas, ImageCanvasError> {
+        let start: usize = pattern.find('#').ok_or_else(| | ImageCanvasError::new("Sequence pattern has no '#' placeholder"))?;
+        let digits: usize = pattern[start ..].chars().take_while(| c | *c == '#').count();
+        let path_for = | index: usize | {
+            format!("{}{:0width$}{}", &pattern[.. start], index, &pattern[start + digits ..], width = digits)
+        };
+
+        let mut index: usize = if Path::new(&path_for(0usize)).exists() { 0usize } else { 1usize };
+        let mut frames: Vec<ImageCanvas> = Vec::new();
+        loop {
+            let path: String = path_for(index);
+            if !Path::new(&path).exists() { break; }
+            frames.push(ImageCanvas::from_file(&path)?);
+            index += 1usize;
+        }
+
+        if frames.is_empty() {
+            return Err(ImageCanvasError::new(&format!("No images found matching {}", pattern)));
+        }
+        let delays: Vec<f32> = vec![frame_time; frames.len()];
+        return Ok(AnimatedCanvas::new(frames, delays));
+    }
+
+    pub fn get_frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn build_mipmaps(&mut self, space: ColorSpace) {
+        for frame in self.frames.iter_mut() {
+            frame.build_mipmaps(space);
+        }
+    }
+
+    fn current_frame(&self) -> &ImageCanvas {
+        &self.frames[self.current]
+    }
+
+}
+
+impl PixelDraw for AnimatedCanvas {
+    fn set_pixel(&mut self, x: u32, y: u32, color: &RGBA8) {
+        self.frames[self.current].set_pixel(x, y, color);
+    }
+
+    fn blend_pixel(&mut self, x: u32, y: u32, color: &RGBA8, mode: BlendMode, space: ColorSpace) {
+        self.frames[self.current].blend_pixel(x, y, color, mode, space);
+    }
+
+    fn get_bounds(&self) -> (u32, u32) {
+        (self.get_width(), self.get_height())
+    }
+}
+
+impl Canvas for AnimatedCanvas {
+    fn get_width(&self) -> u32 {
+        self.current_frame().get_width()
+    }
+
+    fn get_height(&self) -> u32 {
+        self.current_frame().get_height()
+    }
+
+    fn get_pixel(&self, x: u32, y: u32) -> RGBA8 {
+        self.current_frame().get_pixel(x, y)
+    }
+
+    fn fill(&mut self, color: &RGBA8) {
+        self.frames[self.current].fill(color);
+    }
+
+    fn save(&self, path: &str) -> ImageResult<()> {
+        self.current_frame().save(path)
+    }
+
+    fn as_frame(&mut self, speed: i32) -> Frame<'_> {
+        self.frames[self.current].as_frame(speed)
+    }
+
+    fn get_mip_chain(&self) -> Option<&MipChain> {
+        self.current_frame().get_mip_chain()
+    }
+
+    fn set_time(&mut self, time: f32) {
+        if self.duration <= 0f32 { return; }
+        let mut t: f32 = time.rem_euclid(self.duration);
+        self.current = self.frames.len() - 1usize;
+        for (i, delay) in self.delays.iter().enumerate() {
+            if t < *delay {
+                self.current = i;
+                break;
+            }
+            t -= delay;
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use crate::display::canvas::{BlendMode, Canvas, ColorSpace, PixelDraw, RGBA8, RGBA8F};
+
+// Standard multisample positions in sixteenths of a pixel, relative to the pixel center
+const MSAA_2X: [(i8, i8); 2] = [ (4, 4), (-4, -4) ];
+const MSAA_4X: [(i8, i8); 4] = [ (-2, -6), (6, -2), (-6, 2), (2, 6) ];
+const MSAA_8X: [(i8, i8); 8] = [ (1, -3), (-1, 3), (5, 1), (-3, -5), (-5, 5), (-7, -1), (3, 7), (7, -7) ];
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum AntiAliasing {
+    Off,
+    Supersample(u32), // render at this many times the resolution along each axis, so the square of it per pixel, then filter down
+    Multisample(u32) // test coverage at this many points per pixel (2, 4 or 8), shading once
+}
+
+impl AntiAliasing {
+
+    // Factor that the scene is rendered larger by before being resolved
+    pub fn get_scale(&self) -> u32 {
+        return match self {
+            AntiAliasing::Supersample(factor) => *factor,
+            _ => 1u32
+        };
+    }
+
+    pub fn get_sample_offsets(&self) -> Option<Vec<(f32, f32)>> {
+        let pattern: &[(i8, i8)] = match self {
+            AntiAliasing::Multisample(2u32) => &MSAA_2X,
+            AntiAliasing::Multisample(4u32) => &MSAA_4X,
+            AntiAliasing::Multisample(_) => &MSAA_8X,
+            _ => return None
+        };
+        return Some(pattern.iter().map(| (x, y) | ((*x as f32) / 16f32, (*y as f32) / 16f32)).collect());
+    }
+
+}
+
+// Keeps a color for every sample of every pixel until the frame is resolved
+pub struct MultisampleCanvas {
+    w: u32,
+    h: u32,
+    samples: u32,
+    data: Vec<RGBA8>
+}
+
+impl MultisampleCanvas {
+
+    pub fn new(w: u32, h: u32, samples: u32) -> Self {
+        let transparent: RGBA8 = RGBA8 { r: 0u8, g: 0u8, b: 0u8, a: 0u8 };
+        return Self { w, h, samples, data: vec![transparent; (w as usize) * (h as usize) * (samples as usize)] };
+    }
+
+    pub fn fill(&mut self, color: &RGBA8) {
+        self.data.fill(*color);
+    }
+
+    fn compute_safe_index(&self, x: u32, y: u32) -> usize {
+        if x >= self.w || y >= self.h {
+            panic!("MultisampleCanvas indexed at ({}, {}) for size ({}, {})", x, y, self.w, self.h)
+        }
+        ((y as usize) * (self.w as usize) + (x as usize)) * (self.samples as usize)
+    }
+
+    // Averages the samples of every pixel into the target
+    pub fn resolve(&self, target: &mut dyn PixelDraw, space: ColorSpace) {
+        let n: f32 = self.samples as f32;
+        for y in 0 .. self.h {
+            for x in 0 .. self.w {
+                let idx: usize = self.compute_safe_index(x, y);
+                let mut sum: RGBA8F = RGBA8F::zero();
+                for sample in self.data[idx .. idx + (self.samples as usize)].iter() {
+                    let c: RGBA8F = space.decode(sample);
+                    let a: f32 = c.a / 255f32;
+                    sum.r += c.r * a;
+                    sum.g += c.g * a;
+                    sum.b += c.b * a;
+                    sum.a += c.a;
+                }
+                target.set_pixel(x, y, &space.encode(&unpremultiply(&sum, n)));
+            }
+        }
+    }
+
+}
+
+impl PixelDraw for MultisampleCanvas {
+    fn set_pixel(&mut self, x: u32, y: u32, col: &RGBA8) {
+        let idx: usize = self.compute_safe_index(x, y);
+        self.data[idx .. idx + (self.samples as usize)].fill(*col);
+    }
+
+    fn blend_pixel(&mut self, x: u32, y: u32, col: &RGBA8, mode: BlendMode, space: ColorSpace) {
+        self.blend_samples(x, y, u32::MAX, col, mode, space);
+    }
+
+    fn blend_samples(&mut self, x: u32, y: u32, mask: u32, col: &RGBA8, mode: BlendMode, space: ColorSpace) {
+        let idx: usize = self.compute_safe_index(x, y);
+        for i in 0 .. (self.samples as usize) {
+            if mask & (1u32 << i) == 0u32 { continue; }
+            if let Some(c) = mode.apply(col, &self.data[idx + i], space) {
+                self.data[idx + i] = c;
+            }
+        }
+    }
+
+    fn get_bounds(&self) -> (u32, u32) {
+        (self.w, self.h)
+    }
+}
+
+// Filters a canvas rendered at factor times the target resolution down into the target, in the given color space.
+// Uses the Mitchell-Netravali cubic (B = C = 1/3), which is sharper than a box without ringing much.
+pub fn downsample(src: &dyn Canvas, target: &mut dyn PixelDraw, factor: u32, space: ColorSpace) {
+    let sw: usize = src.get_width() as usize;
+    let sh: usize = src.get_height() as usize;
+    let f: usize = factor.max(1u32) as usize;
+    let dw: usize = sw / f;
+    let dh: usize = sh / f;
+
+    // Premultiplied working colors, so that transparent samples don't darken their neighbours
+    let mut pixels: Vec<RGBA8F> = Vec::with_capacity(sw * sh);
+    for y in 0 .. sh {
+        for x in 0 .. sw {
+            let mut c: RGBA8F = space.decode(&src.get_pixel(x as u32, y as u32));
+            let a: f32 = c.a / 255f32;
+            c.scale_rgb(a);
+            pixels.push(c);
+        }
+    }
+
+    // The kernel spans two target pixels on each side, which is the same set of weights for every target pixel
+    let radius: usize = 2usize * f;
+    let weights: Vec<f32> = (0 .. 2usize * radius).map(| i | {
+        mitchell(((i as f32) + 0.5f32 - (radius as f32)) / (f as f32))
+    }).collect();
+    let filter = | get: &dyn Fn(usize) -> RGBA8F, center: usize, len: usize | {
+        let mut sum: RGBA8F = RGBA8F::zero();
+        let mut total: f32 = 0f32;
+        for (i, w) in weights.iter().enumerate() {
+            let pos: i64 = (center as i64) - (radius as i64) + (i as i64);
+            if pos < 0i64 || pos >= (len as i64) { continue; }
+            let c: RGBA8F = get(pos as usize);
+            sum.r += c.r * w;
+            sum.g += c.g * w;
+            sum.b += c.b * w;
+            sum.a += c.a * w;
+            total += w;
+        }
+        if total != 0f32 {
+            sum.r /= total;
+            sum.g /= total;
+            sum.b /= total;
+            sum.a /= total;
+        }
+        sum
+    };
+
+    let mut rows: Vec<RGBA8F> = Vec::with_capacity(dw * sh);
+    for y in 0 .. sh {
+        for x in 0 .. dw {
+            rows.push(filter(&| i | pixels[y * sw + i], x * f + f / 2usize, sw));
+        }
+    }
+    for y in 0 .. dh {
+        for x in 0 .. dw {
+            let c: RGBA8F = filter(&| i | rows[i * dw + x], y * f + f / 2usize, sh);
+            target.set_pixel(x as u32, y as u32, &space.encode(&unpremultiply(&c, 1f32)));
+        }
+    }
+}
+
+// Divides a sum of premultiplied colors by its weight, leaving alpha as a plain average
+fn unpremultiply(sum: &RGBA8F, total: f32) -> RGBA8F {
+    if total <= 0f32 { return RGBA8F::zero(); }
+    let a: f32 = (sum.a / total).max(0f32);
+    let premultiplied: f32 = a / 255f32;
+    if premultiplied <= 0f32 { return RGBA8F::zero(); }
+    return RGBA8F {
+        r: sum.r / total / premultiplied,
+        g: sum.g / total / premultiplied,
+        b: sum.b / total / premultiplied,
+        a
+    };
+}
+
+fn mitchell(x: f32) -> f32 {
+    let b: f32 = 1f32 / 3f32;
+    let c: f32 = 1f32 / 3f32;
+    let x: f32 = x.abs();
+    let ret: f32 = if x < 1f32 {
+        (12f32 - 9f32 * b - 6f32 * c) * x.powi(3i32) + (-18f32 + 12f32 * b + 6f32 * c) * x.powi(2i32) + (6f32 - 2f32 * b)
+    } else if x < 2f32 {
+        (-b - 6f32 * c) * x.powi(3i32) + (6f32 * b + 30f32 * c) * x.powi(2i32) + (-12f32 * b - 48f32 * c) * x + (8f32 * b + 24f32 * c)
+    } else {
+        0f32
+    };
+    return ret / 6f32;
+}
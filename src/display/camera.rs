@@ -2,6 +2,8 @@ use crate::math::transform::Transform;
 use crate::math::vector::Vector3D;
 use std::f32::consts::PI;
 use crate::math::quad::Quad;
+use crate::math::quaternion::Quaternion;
+use crate::math::VectorLike;
 
 pub struct Camera {
     pub transform: Transform,
@@ -114,6 +116,26 @@ impl Camera {
         self.pad_top = pt;
     }
 
+    pub fn look_at(&mut self, target: &Vector3D) {
+        let mut forward: Vector3D = target.copy();
+        forward.subtract_vector(&self.transform.translation);
+        self.transform.rotation = Quaternion::look_rotation(&forward);
+    }
+
+    // Unit vector in world space pointing from the camera through the given screen position
+    pub fn get_ray(&self, x: f32, y: f32) -> Vector3D {
+        let sx: f32 = (x - self.pad_left) / self.max_dim;
+        let sy: f32 = 1f32 - (y - self.pad_top) / self.max_dim;
+        let mut ret: Vector3D = Vector3D::new(
+            ((sx - 0.5f32) * self.near_span) / self.z_near,
+            ((sy - 0.5f32) * self.near_span) / self.z_near,
+            1f32
+        );
+        ret = self.transform.rotation.rotate(&ret);
+        ret.normalize();
+        return ret;
+    }
+
     pub fn project_point(&self, vec: &mut Vector3D) -> bool {
         self.transform.inverse_transform_vector(vec);
         if !self.project_single(&mut vec.x, vec.z) { return false; }
@@ -123,6 +145,9 @@ impl Camera {
         return true;
     }
 
+    // Only points behind the near plane are rejected. Points outside the view are still projected, to positions off
+    // the canvas, since everything drawn from them clips to its canvas and a quad straddling the edge of the view
+    // must keep all of its corners.
     fn project_single(&self, term: &mut f32, z: f32) -> bool {
         if (*term).abs() < f32::EPSILON {
             *term = 0.5f32;
@@ -132,9 +157,7 @@ impl Camera {
             return false;
         }
         let m: f32 = *term / z;
-        let ret: f32 = ((m * self.z_near) / self.near_span) + 0.5f32;
-        if ret < 0f32 || ret > 1f32 { return false; }
-        *term = ret;
+        *term = ((m * self.z_near) / self.near_span) + 0.5f32;
         return true;
     }
 
@@ -146,3 +169,42 @@ impl Camera {
     }
 
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn projects_center_of_view_to_center_of_canvas() {
+        let camera: Camera = Camera::new();
+        let mut point: Vector3D = Vector3D::new(0f32, 0f32, 10f32);
+        assert!(camera.project_point(&mut point));
+        assert!((point.x - 256f32).abs() < 0.001f32);
+        assert!((point.y - 256f32).abs() < 0.001f32);
+    }
+
+    #[test]
+    fn projects_points_outside_view_off_canvas() {
+        let camera: Camera = Camera::new();
+        let mut point: Vector3D = Vector3D::new(100f32, -100f32, 10f32);
+        assert!(camera.project_point(&mut point));
+        assert!(point.x > camera.get_width());
+        assert!(point.y > camera.get_height());
+    }
+
+    #[test]
+    fn rejects_points_behind_near_plane() {
+        let camera: Camera = Camera::new();
+        let mut point: Vector3D = Vector3D::new(1f32, 1f32, -5f32);
+        assert!(!camera.project_point(&mut point));
+
+        let mut quad: Quad = Quad {
+            tl: Vector3D::new(-1f32, 1f32, 5f32),
+            tr: Vector3D::new(1f32, 1f32, 5f32),
+            bl: Vector3D::new(-1f32, -1f32, -5f32),
+            br: Vector3D::new(1f32, -1f32, -5f32)
+        };
+        assert!(!camera.project_quad(&mut quad));
+    }
+
+}
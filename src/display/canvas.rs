@@ -6,13 +6,16 @@ use std::alloc::{alloc, Layout};
 use std::error::Error;
 use std::fmt::{Debug, Display, Formatter};
 use std::path::Path;
+use std::sync::OnceLock;
 use gif::Frame;
 use image::{DynamicImage, ImageResult, Pixel, Rgba, RgbaImage};
 use image::io::Reader as ImageReader;
 use rand::prelude::random;
 use crate::display::render::{GifRenderer, GifRendererOp};
+use crate::math::vector::Vector3D;
 
 
+#[derive(Copy, Clone)]
 pub struct RGBA8 {
     pub r: u8,
     pub g: u8,
@@ -20,13 +23,58 @@ pub struct RGBA8 {
     pub a: u8
 }
 
+#[derive(Copy, Clone)]
 pub struct RGBA8F {
-    r: f32,
-    g: f32,
-    b: f32,
-    a: f32
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32
+}
+
+// Space that color math is done in. Stored colors are always sRGB-encoded bytes.
+#[derive(Copy, Clone, PartialEq)]
+pub enum ColorSpace {
+    Linear, // decode to linear light before filtering, blending and lighting
+    Gamma // work directly on the encoded bytes
+}
+
+impl ColorSpace {
+
+    // Converts a stored color to a working color in the range [0, 255]
+    pub fn decode(&self, col: &RGBA8) -> RGBA8F {
+        if *self == ColorSpace::Gamma { return col.to_float(); }
+        let table: &[f32; 256] = SRGB_TO_LINEAR.get_or_init(| | {
+            let mut ret: [f32; 256] = [0f32; 256];
+            for (i, value) in ret.iter_mut().enumerate() {
+                let c: f32 = (i as f32) / 255f32;
+                let linear: f32 = if c <= 0.04045f32 { c / 12.92f32 } else { ((c + 0.055f32) / 1.055f32).powf(2.4f32) };
+                *value = linear * 255f32;
+            }
+            ret
+        });
+        return RGBA8F {
+            r: table[col.r as usize],
+            g: table[col.g as usize],
+            b: table[col.b as usize],
+            a: col.a as f32
+        };
+    }
+
+    // Converts a working color back to a stored color
+    pub fn encode(&self, col: &RGBA8F) -> RGBA8 {
+        if *self == ColorSpace::Gamma { return col.round(); }
+        let channel = | c: f32 | {
+            let c: f32 = (c / 255f32).clamp(0f32, 1f32);
+            let srgb: f32 = if c <= 0.0031308f32 { c * 12.92f32 } else { 1.055f32 * c.powf(1f32 / 2.4f32) - 0.055f32 };
+            srgb * 255f32
+        };
+        return RGBA8F { r: channel(col.r), g: channel(col.g), b: channel(col.b), a: col.a }.round();
+    }
+
 }
 
+static SRGB_TO_LINEAR: OnceLock<[f32; 256]> = OnceLock::new();
+
 impl RGBA8 {
 
     pub fn random(include_alpha: bool) -> RGBA8 {
@@ -85,6 +133,16 @@ impl RGBA8 {
 
 impl RGBA8F {
 
+    pub fn zero() -> RGBA8F {
+        return RGBA8F { r: 0f32, g: 0f32, b: 0f32, a: 0f32 };
+    }
+
+    pub fn scale_rgb(&mut self, k: f32) {
+        self.r *= k;
+        self.g *= k;
+        self.b *= k;
+    }
+
     pub fn round(&self) -> RGBA8 {
         return RGBA8 {
             r: self.r.round().clamp(0f32, 255f32) as u8,
@@ -107,7 +165,226 @@ impl Display for RGBA8 {
 pub enum SampleMode {
     NearestNeighbor = 0,
     BiLinear = 1,
-    BiCubic = 2
+    BiCubic = 2,
+    MipNearest = 3, // bilinear within the closest mip level
+    Trilinear = 4, // bilinear within the two closest mip levels, blended
+    Anisotropic = 5 // several trilinear samples along the major axis of the pixel footprint
+}
+
+// Upper bound on the number of samples taken by SampleMode::Anisotropic
+pub const MAX_ANISOTROPY: u32 = 16u32;
+
+impl SampleMode {
+
+    pub fn uses_mipmaps(&self) -> bool {
+        return matches!(self, SampleMode::MipNearest | SampleMode::Trilinear | SampleMode::Anisotropic);
+    }
+
+}
+
+#[derive(Copy, Clone)]
+pub enum WrapMode {
+    Repeat,
+    MirroredRepeat,
+    ClampToEdge,
+    ClampToBorder // samples outside the texture take the TextureWrap's border color
+}
+
+impl WrapMode {
+
+    // Maps a pixel coordinate onto the range [0, size), or None if the border color should be used
+    pub fn resolve(&self, coord: i64, size: u32) -> Option<u32> {
+        let n: i64 = size as i64;
+        return match self {
+            WrapMode::Repeat => Some(coord.rem_euclid(n) as u32),
+            WrapMode::MirroredRepeat => {
+                let m: i64 = coord.rem_euclid(n * 2i64);
+                Some((if m < n { m } else { n * 2i64 - 1i64 - m }) as u32)
+            },
+            WrapMode::ClampToEdge => Some(coord.clamp(0i64, n - 1i64) as u32),
+            WrapMode::ClampToBorder => {
+                if coord < 0i64 || coord >= n { None } else { Some(coord as u32) }
+            }
+        };
+    }
+
+}
+
+// Wrap modes for the U and V axes of a texture, and the color used outside it by ClampToBorder
+#[derive(Copy, Clone)]
+pub struct TextureWrap {
+    pub u: WrapMode,
+    pub v: WrapMode,
+    pub border: RGBA8
+}
+
+impl TextureWrap {
+
+    pub fn new(u: WrapMode, v: WrapMode) -> Self {
+        return Self { u, v, border: RGBA8 { r: 0u8, g: 0u8, b: 0u8, a: 0u8 } };
+    }
+
+    pub fn uniform(mode: WrapMode) -> Self {
+        return TextureWrap::new(mode, mode);
+    }
+
+    pub fn clamp() -> Self {
+        return TextureWrap::uniform(WrapMode::ClampToEdge);
+    }
+
+}
+
+// Rate of change of texture UVs per screen pixel
+pub struct UvGradient {
+    pub dudx: f32,
+    pub dvdx: f32,
+    pub dudy: f32,
+    pub dvdy: f32
+}
+
+impl UvGradient {
+
+    pub fn zero() -> Self {
+        return Self { dudx: 0f32, dvdx: 0f32, dudy: 0f32, dvdy: 0f32 };
+    }
+
+    // Chains the screen position partials (d/du, d/dv of a projected quad) with the texture UV partials
+    // (as returned by Model::get_face_uv_partials) to find the texture UV change per screen pixel
+    pub fn from_partials(screen: &[Vector3D; 2], tex: &[f32; 4]) -> Self {
+        let det: f32 = screen[0].x * screen[1].y - screen[1].x * screen[0].y;
+        if det.abs() <= f32::EPSILON { return UvGradient::zero(); }
+
+        let du_dx: f32 = screen[1].y / det;
+        let du_dy: f32 = -screen[1].x / det;
+        let dv_dx: f32 = -screen[0].y / det;
+        let dv_dy: f32 = screen[0].x / det;
+
+        return Self {
+            dudx: tex[0] * du_dx + tex[2] * dv_dx,
+            dvdx: tex[1] * du_dx + tex[3] * dv_dx,
+            dudy: tex[0] * du_dy + tex[2] * dv_dy,
+            dvdy: tex[1] * du_dy + tex[3] * dv_dy
+        };
+    }
+
+    // Splits the footprint into up to max_samples probes along its longer axis, each probe covering
+    // roughly the length of the shorter axis so that the level of detail can stay low
+    pub fn get_anisotropy(&self, w: u32, h: u32, max_samples: u32) -> Anisotropy {
+        let wf: f32 = w as f32;
+        let hf: f32 = h as f32;
+        let x_len: f32 = ((self.dudx * wf).powi(2i32) + (self.dvdx * hf).powi(2i32)).sqrt();
+        let y_len: f32 = ((self.dudy * wf).powi(2i32) + (self.dvdy * hf).powi(2i32)).sqrt();
+
+        let (major, minor, axis_u, axis_v) = if x_len >= y_len {
+            (x_len, y_len, self.dudx, self.dvdx)
+        } else {
+            (y_len, x_len, self.dudy, self.dvdy)
+        };
+        if major <= 1f32 {
+            return Anisotropy { axis_u: 0f32, axis_v: 0f32, samples: 1u32, lod: 0f32 };
+        }
+
+        let ratio: f32 = major / minor.max(f32::EPSILON);
+        let samples: u32 = (ratio.ceil() as u32).clamp(1u32, max_samples.max(1u32));
+        return Anisotropy {
+            axis_u,
+            axis_v,
+            samples,
+            lod: (major / (samples as f32)).max(1f32).log2()
+        };
+    }
+
+    // Level of detail for a texture of the given size, 0 being the base level
+    pub fn get_lod(&self, w: u32, h: u32) -> f32 {
+        let wf: f32 = w as f32;
+        let hf: f32 = h as f32;
+        let x: f32 = (self.dudx * wf).powi(2i32) + (self.dvdx * hf).powi(2i32);
+        let y: f32 = (self.dudy * wf).powi(2i32) + (self.dvdy * hf).powi(2i32);
+        let rho_sqr: f32 = x.max(y);
+        if rho_sqr <= 1f32 { return 0f32; }
+        return 0.5f32 * rho_sqr.log2();
+    }
+
+}
+
+// Footprint of a pixel in texture space, split into samples along its major axis
+pub struct Anisotropy {
+    pub axis_u: f32,
+    pub axis_v: f32,
+    pub samples: u32,
+    pub lod: f32
+}
+
+// Successively halved copies of a canvas, not including the base level
+pub struct MipChain {
+    levels: Vec<HeapCanvas<'static>>
+}
+
+impl MipChain {
+
+    // Each level is a box filter of the one above it, averaged in the given color space. Halving an odd size folds
+    // the last texel into the final one of the level, so the edges keep their full weight.
+    pub fn build(base: &dyn Canvas, space: ColorSpace) -> MipChain {
+        let mut levels: Vec<HeapCanvas<'static>> = Vec::new();
+        let mut w: u32 = base.get_width();
+        let mut h: u32 = base.get_height();
+
+        while w > 1u32 || h > 1u32 {
+            let nw: u32 = (w / 2u32).max(1u32);
+            let nh: u32 = (h / 2u32).max(1u32);
+            let mut level: HeapCanvas<'static> = HeapCanvas::new(nw, nh);
+            {
+                let src: &dyn Canvas = match levels.last() {
+                    Some(prev) => prev,
+                    None => base
+                };
+                let cols: Vec<Vec<u32>> = (0 .. nw).map(| x | box_taps(x, w, nw)).collect();
+                let rows: Vec<Vec<u32>> = (0 .. nh).map(| y | box_taps(y, h, nh)).collect();
+                for (y, row) in rows.iter().enumerate() {
+                    for (x, col) in cols.iter().enumerate() {
+                        let mut sum: RGBA8F = RGBA8F { r: 0f32, g: 0f32, b: 0f32, a: 0f32 };
+                        for sy in row.iter() {
+                            for sx in col.iter() {
+                                let c: RGBA8F = space.decode(&src.get_pixel(*sx, *sy));
+                                sum.r += c.r;
+                                sum.g += c.g;
+                                sum.b += c.b;
+                                sum.a += c.a;
+                            }
+                        }
+                        let count: f32 = (row.len() * col.len()) as f32;
+                        let avg: RGBA8F = RGBA8F { r: sum.r / count, g: sum.g / count, b: sum.b / count, a: sum.a / count };
+                        level.set_pixel(x as u32, y as u32, &space.encode(&avg));
+                    }
+                }
+            }
+            levels.push(level);
+            w = nw;
+            h = nh;
+        }
+
+        MipChain { levels }
+    }
+
+    pub fn get_level_count(&self) -> usize {
+        self.levels.len()
+    }
+
+    // Level 1 is the first level below the base
+    pub fn get_level(&self, level: usize) -> &HeapCanvas<'static> {
+        &self.levels[level - 1usize]
+    }
+
+}
+
+// Texels along one axis that are averaged into texel i of a level halved from size to half
+fn box_taps(i: u32, size: u32, half: u32) -> Vec<u32> {
+    if size == 1u32 { return vec![ 0u32 ]; }
+    let mut ret: Vec<u32> = vec![ i * 2u32, i * 2u32 + 1u32 ];
+    if size % 2u32 == 1u32 && i == half - 1u32 {
+        ret.push(i * 2u32 + 2u32);
+    }
+    ret
 }
 
 pub trait Canvas: PixelDraw {
@@ -118,69 +395,129 @@ pub trait Canvas: PixelDraw {
     fn save(&self, path: &str) -> ImageResult<()>;
     fn as_frame(&mut self, speed: i32) -> Frame;
 
-    fn sample_uv<'a>(&self, mut u: f32, mut v: f32, mode: SampleMode) -> RGBA8 {
-        let iw: u32 = self.get_width();
-        let ih: u32 = self.get_height();
-
-        if iw < 2u32 || ih < 2u32 { return RGBA8::black(); }
-
-        let w: f32 = iw as f32;
-        let h: f32 = ih as f32;
+    fn get_mip_chain(&self) -> Option<&MipChain> {
+        None
+    }
 
-        if u >= 1f32 { u = 1f32 - f32::EPSILON; }
-        let us: f32 = u * (w - 1f32);
-        if v >= 1f32 { v = 1f32 - f32::EPSILON; }
-        let vs: f32 = v * (h - 1f32);
+    // Moves an animated canvas to the frame shown at the given time in seconds
+    fn set_time(&mut self, _time: f32) { }
 
-        if us < 0f32 || us >= w { return RGBA8::black(); }
-        if vs < 0f32 || vs >= h { return RGBA8::black(); }
+    // Like sample_uv_wrap, but picks a mip level from the screen-space UV gradient when the mode uses mipmaps
+    fn sample_uv_grad(&self, u: f32, v: f32, grad: &UvGradient, mode: SampleMode, wrap: &TextureWrap, space: ColorSpace) -> RGBA8F {
+        if !mode.uses_mipmaps() {
+            return self.sample_uv_wrap(u, v, mode, wrap, space);
+        }
 
-        let mut usf: u32 = us.floor() as u32;
-        let mut vsf: u32 = vs.floor() as u32;
-        let usr: f32 = us.fract();
-        let vsr: f32 = vs.fract();
+        let chain: Option<&MipChain> = self.get_mip_chain();
+        let max_lod: f32 = chain.map_or(0f32, | c | c.get_level_count() as f32);
+        let sample_level = | su: f32, sv: f32, level: usize | {
+            match chain {
+                Some(c) if level > 0usize => c.get_level(level).sample_uv_wrap(su, sv, SampleMode::BiLinear, wrap, space),
+                _ => self.sample_uv_wrap(su, sv, SampleMode::BiLinear, wrap, space)
+            }
+        };
+        let sample_trilinear = | su: f32, sv: f32, lod: f32 | {
+            let lod: f32 = lod.clamp(0f32, max_lod);
+            let lower: usize = lod.floor() as usize;
+            let frac: f32 = lod.fract();
+            if frac <= f32::EPSILON {
+                sample_level(su, sv, lower)
+            } else {
+                let a: RGBA8F = sample_level(su, sv, lower);
+                let b: RGBA8F = sample_level(su, sv, lower + 1usize);
+                RGBA8::lerp(&a, &b, frac)
+            }
+        };
 
         return match mode {
-            SampleMode::NearestNeighbor => {
-                if usr >= 0.5f32 { usf += 1u32; }
-                if vsr >= 0.5f32 { vsf += 1u32; }
-                self.get_pixel(usf, vsf)
+            SampleMode::Anisotropic => {
+                let aniso: Anisotropy = grad.get_anisotropy(self.get_width(), self.get_height(), MAX_ANISOTROPY);
+                let mut sum: RGBA8F = RGBA8F::zero();
+                for i in 0 .. aniso.samples {
+                    let t: f32 = ((i as f32) + 0.5f32) / (aniso.samples as f32) - 0.5f32;
+                    let sample: RGBA8F = sample_trilinear(u + aniso.axis_u * t, v + aniso.axis_v * t, aniso.lod);
+                    sum.r += sample.r;
+                    sum.g += sample.g;
+                    sum.b += sample.b;
+                    sum.a += sample.a;
+                }
+                let n: f32 = aniso.samples as f32;
+                RGBA8F { r: sum.r / n, g: sum.g / n, b: sum.b / n, a: sum.a / n }
             },
-            SampleMode::BiLinear => {
-                let tl = self.get_pixel(usf, vsf);
-                let bl = self.get_pixel(usf, vsf + 1u32);
-                let tr = self.get_pixel(usf + 1u32, vsf);
-                let br = self.get_pixel(usf + 1u32, vsf + 1u32);
-
-                let t: RGBA8F = RGBA8::lerp(&tl.to_float(), &tr.to_float(), usr);
-                let b: RGBA8F = RGBA8::lerp(&bl.to_float(), &br.to_float(), usr);
-                RGBA8::lerp(&t, &b, vsr).round()
+            SampleMode::Trilinear => {
+                sample_trilinear(u, v, grad.get_lod(self.get_width(), self.get_height()))
             },
-            SampleMode::BiCubic => {
-                let samples: &'a mut [RGBA8F];
-                unsafe {
-                    let layout: Layout = Layout::array::<RGBA8F>(16).unwrap();
-                    let ptr: *mut RGBA8F = alloc(layout) as *mut RGBA8F;
-                    samples = core::slice::from_raw_parts_mut::<'a, RGBA8F>(ptr, 16);
-                }
+            _ => {
+                let lod: f32 = grad.get_lod(self.get_width(), self.get_height()).min(max_lod);
+                sample_level(u, v, lod.round() as usize)
+            }
+        };
+    }
 
-                for ux in -1i32 .. 3i32 {
-                    let mut ui: u32 = if usf > 0u32 || ux >= 0i32 { ((usf as i32) + ux) as u32 } else { usf };
-                    if ui >= iw { ui -= 1u32; }
+    // Filters the encoded bytes directly, as sampling did before color spaces
+    fn sample_uv(&self, u: f32, v: f32, mode: SampleMode) -> RGBA8 {
+        let space: ColorSpace = ColorSpace::Gamma;
+        space.encode(&self.sample_uv_wrap(u, v, mode, &TextureWrap::clamp(), space))
+    }
 
-                    for vx in -1i32 .. 3i32 {
-                        let mut vi: u32 = if vsf > 0u32 || vx >= 0i32 { ((vsf as i32) + vx) as u32 } else { vsf };
-                        if vi >= ih { vi -= 1u32; }
+    // Reads a pixel at a coordinate that may lie outside of the canvas, resolving it with the wrap modes
+    fn get_texel(&self, x: i64, y: i64, wrap: &TextureWrap) -> RGBA8 {
+        let xi: Option<u32> = wrap.u.resolve(x, self.get_width());
+        let yi: Option<u32> = wrap.v.resolve(y, self.get_height());
+        return match (xi, yi) {
+            (Some(xi), Some(yi)) => self.get_pixel(xi, yi),
+            _ => wrap.border
+        };
+    }
 
-                        samples[((ux + 1i32) * 4i32 + vx + 1i32) as usize] = self.get_pixel(ui, vi).to_float();
-                    }
-                }
+    // Filters the texture around a UV coordinate, returning the result in the given working color space
+    fn sample_uv_wrap(&self, u: f32, v: f32, mode: SampleMode, wrap: &TextureWrap, space: ColorSpace) -> RGBA8F {
+        let iw: u32 = self.get_width();
+        let ih: u32 = self.get_height();
+
+        if iw < 1u32 || ih < 1u32 { return space.decode(&wrap.border); }
+
+        // Texel centers lie at half-integer coordinates, so that repeating textures tile with a period of one
+        let us: f32 = u * (iw as f32) - 0.5f32;
+        let vs: f32 = v * (ih as f32) - 0.5f32;
+        if !us.is_finite() || !vs.is_finite() { return space.decode(&wrap.border); }
 
-                let a: RGBA8F = RGBA8::cubic_int(&samples[0], &samples[4], &samples[8], &samples[12], usr);
-                let b: RGBA8F = RGBA8::cubic_int(&samples[1], &samples[5], &samples[9], &samples[13], usr);
-                let c: RGBA8F = RGBA8::cubic_int(&samples[2], &samples[6], &samples[10], &samples[14], usr);
-                let d: RGBA8F = RGBA8::cubic_int(&samples[3], &samples[7], &samples[11], &samples[15], usr);
-                RGBA8::cubic_int(&a, &b, &c, &d, vsr).round()
+        let usf: i64 = us.floor() as i64;
+        let vsf: i64 = vs.floor() as i64;
+        let usr: f32 = us - us.floor();
+        let vsr: f32 = vs - vs.floor();
+
+        return match mode {
+            SampleMode::NearestNeighbor => {
+                space.decode(&self.get_texel(us.round() as i64, vs.round() as i64, wrap))
+            },
+            SampleMode::BiLinear | SampleMode::MipNearest | SampleMode::Trilinear | SampleMode::Anisotropic => {
+                let tl = self.get_texel(usf, vsf, wrap);
+                let bl = self.get_texel(usf, vsf + 1i64, wrap);
+                let tr = self.get_texel(usf + 1i64, vsf, wrap);
+                let br = self.get_texel(usf + 1i64, vsf + 1i64, wrap);
+
+                let t: RGBA8F = RGBA8::lerp(&space.decode(&tl), &space.decode(&tr), usr);
+                let b: RGBA8F = RGBA8::lerp(&space.decode(&bl), &space.decode(&br), usr);
+                RGBA8::lerp(&t, &b, vsr)
+            },
+            SampleMode::BiCubic => {
+                let sample_row = | vx: i64 | {
+                    let y: i64 = vsf + vx;
+                    RGBA8::cubic_int(
+                        &space.decode(&self.get_texel(usf - 1i64, y, wrap)),
+                        &space.decode(&self.get_texel(usf, y, wrap)),
+                        &space.decode(&self.get_texel(usf + 1i64, y, wrap)),
+                        &space.decode(&self.get_texel(usf + 2i64, y, wrap)),
+                        usr
+                    )
+                };
+
+                let a: RGBA8F = sample_row(-1i64);
+                let b: RGBA8F = sample_row(0i64);
+                let c: RGBA8F = sample_row(1i64);
+                let d: RGBA8F = sample_row(2i64);
+                RGBA8::cubic_int(&a, &b, &c, &d, vsr)
             }
         }
     }
@@ -189,7 +526,8 @@ pub trait Canvas: PixelDraw {
 pub struct HeapCanvas<'a> {
     w: u32,
     h: u32,
-    data: &'a mut [u8]
+    data: &'a mut [u8],
+    mips: Option<MipChain>
 }
 
 impl HeapCanvas<'_> {
@@ -201,22 +539,32 @@ impl HeapCanvas<'_> {
             let ptr: *mut u8 = alloc(Layout::array::<u8>(s).unwrap());
             data = core::slice::from_raw_parts_mut::<'a, u8>(ptr, s);
         }
-        HeapCanvas { w, h, data }
+        HeapCanvas { w, h, data, mips: None }
+    }
+
+    pub fn build_mipmaps(&mut self, space: ColorSpace) {
+        self.mips = Some(MipChain::build(self, space));
     }
 
 }
 
 pub struct ImageCanvas {
-    buffer: RgbaImage
+    buffer: RgbaImage,
+    mips: Option<MipChain>
 }
 
 impl ImageCanvas {
     pub fn new(img: DynamicImage) -> ImageCanvas {
         ImageCanvas {
-            buffer: img.into_rgba8()
+            buffer: img.into_rgba8(),
+            mips: None
         }
     }
 
+    pub fn build_mipmaps(&mut self, space: ColorSpace) {
+        self.mips = Some(MipChain::build(self, space));
+    }
+
     pub fn from_file_assert<P>(path: P) -> ImageCanvas where P: AsRef<Path> {
         return ImageCanvas::new(ImageReader::open(path).unwrap().decode().unwrap());
     }
@@ -240,6 +588,12 @@ pub struct ImageCanvasError {
     msg: Box<str>
 }
 
+impl ImageCanvasError {
+    pub fn new(msg: &str) -> ImageCanvasError {
+        ImageCanvasError { msg: msg.into() }
+    }
+}
+
 impl Debug for ImageCanvasError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.msg)
@@ -256,6 +610,16 @@ impl PixelDraw for ImageCanvas {
     fn set_pixel(&mut self, x: u32, y: u32, color: &RGBA8) {
         self.buffer.put_pixel(x, y, Rgba::<u8>::from([ color.r, color.g, color.b, color.a ]));
     }
+
+    fn blend_pixel(&mut self, x: u32, y: u32, color: &RGBA8, mode: BlendMode, space: ColorSpace) {
+        if let Some(c) = mode.apply(color, &self.get_pixel(x, y), space) {
+            self.set_pixel(x, y, &c);
+        }
+    }
+
+    fn get_bounds(&self) -> (u32, u32) {
+        (self.get_width(), self.get_height())
+    }
 }
 
 impl Canvas for ImageCanvas {
@@ -300,6 +664,10 @@ impl Canvas for ImageCanvas {
         if h > u16::MAX as u32 { panic!("Height ({}) eclipses 16-bit integer limit", h) }
         Frame::from_rgba_speed(w as u16, h as u16, self.buffer.to_vec().as_mut_slice(), speed)
     }
+
+    fn get_mip_chain(&self) -> Option<&MipChain> {
+        self.mips.as_ref()
+    }
 }
 
 impl Debug for ImageCanvas {
@@ -337,6 +705,16 @@ impl PixelDraw for HeapCanvas<'_> {
         self.data[idx + 2] = color.b;
         self.data[idx + 3] = color.a;
     }
+
+    fn blend_pixel(&mut self, x: u32, y: u32, color: &RGBA8, mode: BlendMode, space: ColorSpace) {
+        if let Some(c) = mode.apply(color, &self.get_pixel(x, y), space) {
+            self.set_pixel(x, y, &c);
+        }
+    }
+
+    fn get_bounds(&self) -> (u32, u32) {
+        (self.get_width(), self.get_height())
+    }
 }
 
 impl Canvas for HeapCanvas<'_> {
@@ -382,6 +760,10 @@ impl Canvas for HeapCanvas<'_> {
         if self.h > u16::MAX as u32 { panic!("Height ({}) eclipses 16-bit integer limit", self.h) }
         Frame::from_rgba_speed(self.w as u16, self.h as u16, self.data, speed)
     }
+
+    fn get_mip_chain(&self) -> Option<&MipChain> {
+        self.mips.as_ref()
+    }
 }
 
 impl Drop for HeapCanvas<'_> {
@@ -394,8 +776,158 @@ impl Drop for HeapCanvas<'_> {
 
 }
 
+pub struct FloatCanvas {
+    w: u32,
+    h: u32,
+    data: Vec<f32>
+}
+
+impl FloatCanvas {
+
+    pub fn new(w: u32, h: u32) -> FloatCanvas {
+        FloatCanvas { w, h, data: vec![0f32; (w as usize) * (h as usize)] }
+    }
+
+    pub fn get_width(&self) -> u32 {
+        self.w
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.h
+    }
+
+    fn compute_safe_index(&self, x: u32, y: u32) -> usize {
+        if x >= self.w || y >= self.h {
+            panic!("FloatCanvas indexed at ({}, {}) for size ({}, {})", x, y, self.w, self.h)
+        }
+        (y as usize) * (self.w as usize) + (x as usize)
+    }
+
+    pub fn get_value(&self, x: u32, y: u32) -> f32 {
+        self.data[self.compute_safe_index(x, y)]
+    }
+
+    pub fn set_value(&mut self, x: u32, y: u32, value: f32) {
+        let idx: usize = self.compute_safe_index(x, y);
+        self.data[idx] = value;
+    }
+
+    pub fn fill(&mut self, value: f32) {
+        self.data.fill(value);
+    }
+
+    // Writes the value only if it is less than the value already present, like a depth test
+    pub fn set_value_min(&mut self, x: u32, y: u32, value: f32) -> bool {
+        let idx: usize = self.compute_safe_index(x, y);
+        if value < self.data[idx] {
+            self.data[idx] = value;
+            return true;
+        }
+        false
+    }
+
+    // Rasterizes a screen-space triangle, keeping the smaller of the existing value and the z interpolated across it
+    pub fn draw_triangle_min(&mut self, a: &Vector3D, b: &Vector3D, c: &Vector3D) {
+        let area: f32 = edge(a, b, c.x, c.y);
+        if area.abs() <= f32::EPSILON { return; }
+
+        let w: f32 = self.w as f32;
+        let h: f32 = self.h as f32;
+        let min_x: f32 = a.x.min(b.x).min(c.x).floor().max(0f32);
+        let min_y: f32 = a.y.min(b.y).min(c.y).floor().max(0f32);
+        let max_x: f32 = a.x.max(b.x).max(c.x).ceil().min(w - 1f32);
+        let max_y: f32 = a.y.max(b.y).max(c.y).ceil().min(h - 1f32);
+        if min_x > max_x || min_y > max_y { return; }
+
+        for y in (min_y as u32) ..= (max_y as u32) {
+            let py: f32 = y as f32;
+            for x in (min_x as u32) ..= (max_x as u32) {
+                let px: f32 = x as f32;
+                let wa: f32 = edge(b, c, px, py) / area;
+                let wb: f32 = edge(c, a, px, py) / area;
+                let wc: f32 = 1f32 - wa - wb;
+                if wa < 0f32 || wb < 0f32 || wc < 0f32 { continue; }
+                self.set_value_min(x, y, (wa * a.z) + (wb * b.z) + (wc * c.z));
+            }
+        }
+    }
+
+}
+
+fn edge(a: &Vector3D, b: &Vector3D, x: f32, y: f32) -> f32 {
+    (b.x - a.x) * (y - a.y) - (b.y - a.y) * (x - a.x)
+}
+
+#[derive(Copy, Clone)]
+pub enum BlendMode {
+    Replace,
+    AlphaTest(u8), // replace if the source alpha is at least this much, otherwise discard
+    AlphaOver,
+    Additive,
+    Multiply,
+    Screen
+}
+
+impl BlendMode {
+
+    // Combines a source color with the destination it is drawn over, or None if the pixel should be left alone.
+    // The math happens in the given color space.
+    pub fn apply(&self, src: &RGBA8, dst: &RGBA8, space: ColorSpace) -> Option<RGBA8> {
+        let sa: f32 = (src.a as f32) / 255f32;
+        let da: f32 = (dst.a as f32) / 255f32;
+        let s: RGBA8F = space.decode(src);
+        let d: RGBA8F = space.decode(dst);
+
+        let composite = | f: fn(f32, f32) -> f32 | {
+            // Porter-Duff source over, with the source color first combined with the destination through f
+            let out_a: f32 = sa + da * (1f32 - sa);
+            if out_a <= f32::EPSILON {
+                return RGBA8 { r: 0u8, g: 0u8, b: 0u8, a: 0u8 };
+            }
+            let channel = | sc: f32, dc: f32 | {
+                let sf: f32 = sc / 255f32;
+                let df: f32 = dc / 255f32;
+                let mixed: f32 = sf * (1f32 - da) + f(sf, df) * da;
+                (mixed * sa + df * da * (1f32 - sa)) / out_a * 255f32
+            };
+            space.encode(&RGBA8F {
+                r: channel(s.r, d.r),
+                g: channel(s.g, d.g),
+                b: channel(s.b, d.b),
+                a: out_a * 255f32
+            })
+        };
+
+        return match self {
+            BlendMode::Replace => Some(*src),
+            BlendMode::AlphaTest(threshold) => {
+                if src.a >= *threshold { Some(*src) } else { None }
+            },
+            BlendMode::AlphaOver => Some(composite(| s, _ | s)),
+            BlendMode::Additive => {
+                Some(space.encode(&RGBA8F {
+                    r: d.r + s.r * sa,
+                    g: d.g + s.g * sa,
+                    b: d.b + s.b * sa,
+                    a: (dst.a.max(src.a)) as f32
+                }))
+            },
+            BlendMode::Multiply => Some(composite(| s, d | s * d)),
+            BlendMode::Screen => Some(composite(| s, d | 1f32 - (1f32 - s) * (1f32 - d)))
+        };
+    }
+
+}
+
 pub trait PixelDraw {
     fn set_pixel(&mut self, x: u32, y: u32, col: &RGBA8);
+    fn blend_pixel(&mut self, x: u32, y: u32, col: &RGBA8, mode: BlendMode, space: ColorSpace);
+    fn get_bounds(&self) -> (u32, u32); // width and height that pixels may be drawn within
+
+    // Blends onto the samples of a pixel set in the mask, for targets that keep several samples per pixel
+    fn blend_samples(&mut self, x: u32, y: u32, mask: u32, col: &RGBA8, mode: BlendMode, space: ColorSpace) {
+        if mask != 0u32 { self.blend_pixel(x, y, col, mode, space); }
+    }
 }
 
 impl PixelDraw for GifRenderer<'_> {
@@ -404,4 +936,49 @@ impl PixelDraw for GifRenderer<'_> {
         self.write(GifRendererOp::SetPixel(x, y, col));
     }
 
+    fn blend_pixel(&mut self, x: u32, y: u32, col: &RGBA8, mode: BlendMode, space: ColorSpace) {
+        self.write(GifRendererOp::BlendPixel(x, y, col, mode, space));
+    }
+
+    fn get_bounds(&self) -> (u32, u32) {
+        (self.get_width(), self.get_height())
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column_canvas(w: u32, h: u32, white_x: u32) -> HeapCanvas<'static> {
+        let mut ret: HeapCanvas<'static> = HeapCanvas::new(w, h);
+        for y in 0 .. h {
+            for x in 0 .. w {
+                let c: u8 = if x == white_x { 255u8 } else { 0u8 };
+                ret.set_pixel(x, y, &RGBA8 { r: c, g: c, b: c, a: 255u8 });
+            }
+        }
+        ret
+    }
+
+    #[test]
+    fn mip_keeps_last_column_of_odd_width() {
+        let base: HeapCanvas<'static> = column_canvas(5u32, 1u32, 4u32);
+        let chain: MipChain = MipChain::build(&base, ColorSpace::Gamma);
+        let level: &HeapCanvas<'static> = chain.get_level(1usize);
+        assert_eq!(level.get_width(), 2u32);
+        assert_eq!(level.get_pixel(0u32, 0u32).r, 0u8);
+        assert_eq!(level.get_pixel(1u32, 0u32).r, 85u8);
+    }
+
+    #[test]
+    fn mip_of_odd_square_averages_every_texel() {
+        let base: HeapCanvas<'static> = column_canvas(3u32, 3u32, 2u32);
+        let chain: MipChain = MipChain::build(&base, ColorSpace::Gamma);
+        assert_eq!(chain.get_level_count(), 1usize);
+        let texel: RGBA8 = chain.get_level(1usize).get_pixel(0u32, 0u32);
+        assert_eq!(texel.r, 85u8);
+        assert_eq!(texel.a, 255u8);
+    }
+
 }
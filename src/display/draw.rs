@@ -0,0 +1,403 @@
+use crate::display::canvas::{BlendMode, ColorSpace, PixelDraw, RGBA8};
+
+// Vertical samples taken per pixel row when filling polygons
+const POLYGON_SUBSAMPLES: u32 = 4u32;
+
+// Anti-aliased shapes drawn over whatever is already on a PixelDraw. Coordinates are in pixels with
+// pixel centers at +0.5, and partially covered pixels are alpha blended in the given color space, which should be
+// the one the rest of the frame is drawn in.
+pub trait Draw: PixelDraw {
+
+    // Blends a color onto a single pixel with its alpha scaled by how much of the pixel is covered
+    fn cover_pixel(&mut self, x: i64, y: i64, coverage: f32, col: &RGBA8, space: ColorSpace) {
+        let (w, h) = self.get_bounds();
+        if coverage <= 0f32 || x < 0i64 || y < 0i64 || x >= (w as i64) || y >= (h as i64) { return; }
+        let mut c: RGBA8 = *col;
+        c.a = ((col.a as f32) * coverage.min(1f32)).round() as u8;
+        if c.a == 0u8 { return; }
+        self.blend_pixel(x as u32, y as u32, &c, BlendMode::AlphaOver, space);
+    }
+
+    // One pixel wide line using Xiaolin Wu's algorithm
+    fn draw_line(&mut self, x0: f32, y0: f32, x1: f32, y1: f32, col: &RGBA8, space: ColorSpace) {
+        let steep: bool = (y1 - y0).abs() > (x1 - x0).abs();
+        // Work along the major axis, and shift so that pixel centers land on integers
+        let (mut ax, mut ay, mut bx, mut by) = if steep {
+            (y0 - 0.5f32, x0 - 0.5f32, y1 - 0.5f32, x1 - 0.5f32)
+        } else {
+            (x0 - 0.5f32, y0 - 0.5f32, x1 - 0.5f32, y1 - 0.5f32)
+        };
+        if ax > bx {
+            std::mem::swap(&mut ax, &mut bx);
+            std::mem::swap(&mut ay, &mut by);
+        }
+
+        let dx: f32 = bx - ax;
+        let gradient: f32 = if dx <= f32::EPSILON { 1f32 } else { (by - ay) / dx };
+        let mut plot = | major: i64, minor: i64, coverage: f32 | {
+            if steep {
+                self.cover_pixel(minor, major, coverage, col, space);
+            } else {
+                self.cover_pixel(major, minor, coverage, col, space);
+            }
+        };
+
+        // First endpoint
+        let x_end: f32 = ax.round();
+        let y_end: f32 = ay + gradient * (x_end - ax);
+        let x_gap: f32 = 1f32 - fraction(ax + 0.5f32);
+        let start: i64 = x_end as i64;
+        plot(start, y_end.floor() as i64, (1f32 - fraction(y_end)) * x_gap);
+        plot(start, y_end.floor() as i64 + 1i64, fraction(y_end) * x_gap);
+        let mut inter_y: f32 = y_end + gradient;
+
+        // Second endpoint
+        let x_end: f32 = bx.round();
+        let y_end: f32 = by + gradient * (x_end - bx);
+        let x_gap: f32 = fraction(bx + 0.5f32);
+        let end: i64 = x_end as i64;
+        plot(end, y_end.floor() as i64, (1f32 - fraction(y_end)) * x_gap);
+        plot(end, y_end.floor() as i64 + 1i64, fraction(y_end) * x_gap);
+
+        for x in (start + 1i64) .. end {
+            plot(x, inter_y.floor() as i64, 1f32 - fraction(inter_y));
+            plot(x, inter_y.floor() as i64 + 1i64, fraction(inter_y));
+            inter_y += gradient;
+        }
+    }
+
+    // Connected line segments of any width, with round joins and caps
+    fn draw_polyline(&mut self, points: &[(f32, f32)], width: f32, col: &RGBA8, space: ColorSpace) {
+        if points.is_empty() { return; }
+        if width <= 1f32 {
+            for pair in points.windows(2usize) {
+                self.draw_line(pair[0].0, pair[0].1, pair[1].0, pair[1].1, col, space);
+            }
+            return;
+        }
+
+        let half: f32 = width / 2f32;
+        let (bw, bh) = self.get_bounds();
+        let min_x: f32 = points.iter().map(| p | p.0).fold(f32::INFINITY, f32::min) - half - 1f32;
+        let min_y: f32 = points.iter().map(| p | p.1).fold(f32::INFINITY, f32::min) - half - 1f32;
+        let max_x: f32 = points.iter().map(| p | p.0).fold(f32::NEG_INFINITY, f32::max) + half + 1f32;
+        let max_y: f32 = points.iter().map(| p | p.1).fold(f32::NEG_INFINITY, f32::max) + half + 1f32;
+
+        // Each pixel is covered once based on its distance to the nearest segment, so joins don't blend twice
+        for y in (min_y.floor().max(0f32) as i64) .. (max_y.ceil().min(bh as f32) as i64) {
+            let py: f32 = (y as f32) + 0.5f32;
+            for x in (min_x.floor().max(0f32) as i64) .. (max_x.ceil().min(bw as f32) as i64) {
+                let px: f32 = (x as f32) + 0.5f32;
+                let mut dist: f32 = distance_to_segment(px, py, points[0], points[0]);
+                for pair in points.windows(2usize) {
+                    dist = dist.min(distance_to_segment(px, py, pair[0], pair[1]));
+                }
+                self.cover_pixel(x, y, (half - dist + 0.5f32).clamp(0f32, 1f32), col, space);
+            }
+        }
+    }
+
+    fn fill_circle(&mut self, cx: f32, cy: f32, radius: f32, col: &RGBA8, space: ColorSpace) {
+        let (bw, bh) = self.get_bounds();
+        for y in ((cy - radius - 1f32).floor().max(0f32) as i64) .. ((cy + radius + 1f32).ceil().min(bh as f32) as i64) {
+            let dy: f32 = (y as f32) + 0.5f32 - cy;
+            for x in ((cx - radius - 1f32).floor().max(0f32) as i64) .. ((cx + radius + 1f32).ceil().min(bw as f32) as i64) {
+                let dx: f32 = (x as f32) + 0.5f32 - cx;
+                let dist: f32 = (dx * dx + dy * dy).sqrt();
+                self.cover_pixel(x, y, (radius - dist + 0.5f32).clamp(0f32, 1f32), col, space);
+            }
+        }
+    }
+
+    fn stroke_circle(&mut self, cx: f32, cy: f32, radius: f32, width: f32, col: &RGBA8, space: ColorSpace) {
+        let half: f32 = width.max(1f32) / 2f32;
+        let outer: f32 = radius + half;
+        let (bw, bh) = self.get_bounds();
+        for y in ((cy - outer - 1f32).floor().max(0f32) as i64) .. ((cy + outer + 1f32).ceil().min(bh as f32) as i64) {
+            let dy: f32 = (y as f32) + 0.5f32 - cy;
+            for x in ((cx - outer - 1f32).floor().max(0f32) as i64) .. ((cx + outer + 1f32).ceil().min(bw as f32) as i64) {
+                let dx: f32 = (x as f32) + 0.5f32 - cx;
+                let dist: f32 = ((dx * dx + dy * dy).sqrt() - radius).abs();
+                self.cover_pixel(x, y, (half - dist + 0.5f32).clamp(0f32, 1f32), col, space);
+            }
+        }
+    }
+
+    // Axis-aligned rectangle, with fractional edges partially covering their pixels
+    fn fill_rect(&mut self, x: f32, y: f32, w: f32, h: f32, col: &RGBA8, space: ColorSpace) {
+        if w <= 0f32 || h <= 0f32 { return; }
+        let (bw, bh) = self.get_bounds();
+        for py in (y.floor().max(0f32) as i64) .. ((y + h).ceil().min(bh as f32) as i64) {
+            let cov_y: f32 = ((y + h).min((py + 1i64) as f32) - y.max(py as f32)).max(0f32);
+            for px in (x.floor().max(0f32) as i64) .. ((x + w).ceil().min(bw as f32) as i64) {
+                let cov_x: f32 = ((x + w).min((px + 1i64) as f32) - x.max(px as f32)).max(0f32);
+                self.cover_pixel(px, py, cov_x * cov_y, col, space);
+            }
+        }
+    }
+
+    // Rectangle outline centered on the rectangle's edges, drawn as four bands that don't overlap
+    fn stroke_rect(&mut self, x: f32, y: f32, w: f32, h: f32, width: f32, col: &RGBA8, space: ColorSpace) {
+        let half: f32 = width / 2f32;
+        if w <= width || h <= width {
+            self.fill_rect(x - half, y - half, w + width, h + width, col, space);
+            return;
+        }
+        self.fill_rect(x - half, y - half, w + width, width, col, space);
+        self.fill_rect(x - half, y + h - half, w + width, width, col, space);
+        self.fill_rect(x - half, y + half, width, h - width, col, space);
+        self.fill_rect(x + w - half, y + half, width, h - width, col, space);
+    }
+
+    // Fills a polygon using the even-odd rule, so it may be concave or self-intersecting
+    fn fill_polygon(&mut self, points: &[(f32, f32)], col: &RGBA8, space: ColorSpace) {
+        if points.len() < 3usize { return; }
+        let (bw, bh) = self.get_bounds();
+        let min_y: f32 = points.iter().map(| p | p.1).fold(f32::INFINITY, f32::min).floor().max(0f32);
+        let max_y: f32 = points.iter().map(| p | p.1).fold(f32::NEG_INFINITY, f32::max).ceil().min(bh as f32);
+        let min_x: i64 = points.iter().map(| p | p.0).fold(f32::INFINITY, f32::min).floor().max(0f32) as i64;
+        let max_x: i64 = points.iter().map(| p | p.0).fold(f32::NEG_INFINITY, f32::max).ceil().min(bw as f32) as i64;
+        if min_x >= max_x { return; }
+
+        let mut coverage: Vec<f32> = vec![0f32; (max_x - min_x) as usize];
+        let mut crossings: Vec<f32> = Vec::new();
+        for y in (min_y as i64) .. (max_y as i64) {
+            coverage.iter_mut().for_each(| c | *c = 0f32);
+            for s in 0 .. POLYGON_SUBSAMPLES {
+                let sy: f32 = (y as f32) + ((s as f32) + 0.5f32) / (POLYGON_SUBSAMPLES as f32);
+                crossings.clear();
+                for i in 0 .. points.len() {
+                    let a: (f32, f32) = points[i];
+                    let b: (f32, f32) = points[(i + 1usize) % points.len()];
+                    if (a.1 <= sy) != (b.1 <= sy) {
+                        crossings.push(a.0 + (sy - a.1) / (b.1 - a.1) * (b.0 - a.0));
+                    }
+                }
+                crossings.sort_unstable_by(| a, b | a.total_cmp(b));
+
+                // Spread each covered span over the pixels it overlaps
+                for span in crossings.chunks_exact(2usize) {
+                    let xa: f32 = span[0].max(min_x as f32);
+                    let xb: f32 = span[1].min(max_x as f32);
+                    if xa >= xb { continue; }
+                    for px in (xa.floor() as i64) .. (xb.ceil() as i64) {
+                        let overlap: f32 = xb.min((px + 1i64) as f32) - xa.max(px as f32);
+                        coverage[(px - min_x) as usize] += overlap / (POLYGON_SUBSAMPLES as f32);
+                    }
+                }
+            }
+            for (i, c) in coverage.iter().enumerate() {
+                self.cover_pixel(min_x + (i as i64), y, *c, col, space);
+            }
+        }
+    }
+
+    // Closed outline through the polygon's points
+    fn stroke_polygon(&mut self, points: &[(f32, f32)], width: f32, col: &RGBA8, space: ColorSpace) {
+        if points.is_empty() { return; }
+        let mut closed: Vec<(f32, f32)> = points.to_vec();
+        closed.push(points[0]);
+        self.draw_polyline(&closed, width, col, space);
+    }
+
+}
+
+impl<T: PixelDraw + ?Sized> Draw for T { }
+
+// Distance above the floor, which unlike fract stays positive for points left of or above the canvas
+fn fraction(v: f32) -> f32 {
+    v - v.floor()
+}
+
+fn distance_to_segment(px: f32, py: f32, a: (f32, f32), b: (f32, f32)) -> f32 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length_sq: f32 = dx * dx + dy * dy;
+    let t: f32 = if length_sq <= f32::EPSILON {
+        0f32
+    } else {
+        (((px - a.0) * dx + (py - a.1) * dy) / length_sq).clamp(0f32, 1f32)
+    };
+    let (cx, cy) = (a.0 + dx * t - px, a.1 + dy * t - py);
+    (cx * cx + cy * cy).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Records the alpha blended onto each pixel, which is the coverage scaled to 255 for an opaque color, and the
+    // color space of the last blend
+    struct CoverageCanvas {
+        w: u32,
+        h: u32,
+        alpha: Vec<u32>,
+        space: Option<ColorSpace>
+    }
+
+    impl CoverageCanvas {
+        fn new(w: u32, h: u32) -> Self {
+            Self { w, h, alpha: vec![0u32; (w * h) as usize], space: None }
+        }
+
+        fn at(&self, x: u32, y: u32) -> u32 {
+            self.alpha[(y * self.w + x) as usize]
+        }
+
+        fn row(&self, y: u32) -> Vec<u32> {
+            (0 .. self.w).map(| x | self.at(x, y)).collect()
+        }
+
+        fn column(&self, x: u32) -> Vec<u32> {
+            (0 .. self.h).map(| y | self.at(x, y)).collect()
+        }
+
+        fn total(&self) -> u32 {
+            self.alpha.iter().sum()
+        }
+    }
+
+    impl PixelDraw for CoverageCanvas {
+        fn set_pixel(&mut self, x: u32, y: u32, col: &RGBA8) {
+            self.alpha[(y * self.w + x) as usize] = col.a as u32;
+        }
+
+        fn blend_pixel(&mut self, x: u32, y: u32, col: &RGBA8, _mode: BlendMode, space: ColorSpace) {
+            self.alpha[(y * self.w + x) as usize] += col.a as u32;
+            self.space = Some(space);
+        }
+
+        fn get_bounds(&self) -> (u32, u32) {
+            (self.w, self.h)
+        }
+    }
+
+    const WHITE: RGBA8 = RGBA8 { r: 255u8, g: 255u8, b: 255u8, a: 255u8 };
+
+    #[test]
+    fn line_inside_row_zero_is_drawn() {
+        let mut canvas: CoverageCanvas = CoverageCanvas::new(8u32, 4u32);
+        canvas.draw_line(1.5f32, 0.2f32, 6.5f32, 0.2f32, &WHITE, ColorSpace::Gamma);
+        // 0.3 from the center of row 0, and 0.7 from the row above it. The ends sit on pixel centers, so they cover half.
+        assert_eq!(canvas.row(0u32)[1 .. 7], [ 89u32, 179u32, 179u32, 179u32, 179u32, 89u32 ]);
+        assert_eq!(canvas.row(1u32), vec![0u32; 8]);
+    }
+
+    #[test]
+    fn line_above_the_canvas_still_covers_row_zero() {
+        let mut canvas: CoverageCanvas = CoverageCanvas::new(8u32, 4u32);
+        canvas.draw_line(1.5f32, -0.3f32, 6.5f32, -0.3f32, &WHITE, ColorSpace::Gamma);
+        assert_eq!(canvas.row(0u32)[1 .. 7], [ 25u32, 51u32, 51u32, 51u32, 51u32, 25u32 ]);
+        assert_eq!(canvas.row(1u32), vec![0u32; 8]);
+    }
+
+    #[test]
+    fn line_inside_column_zero_is_drawn() {
+        let mut canvas: CoverageCanvas = CoverageCanvas::new(4u32, 8u32);
+        canvas.draw_line(0.2f32, 1.5f32, 0.2f32, 6.5f32, &WHITE, ColorSpace::Gamma);
+        assert_eq!(canvas.column(0u32)[1 .. 7], [ 89u32, 179u32, 179u32, 179u32, 179u32, 89u32 ]);
+        assert_eq!(canvas.column(1u32), vec![0u32; 8]);
+    }
+
+    #[test]
+    fn line_crossing_the_border_covers_the_pixels_inside() {
+        let mut canvas: CoverageCanvas = CoverageCanvas::new(8u32, 8u32);
+        canvas.draw_line(-4.5f32, 3.5f32, 4.5f32, 3.5f32, &WHITE, ColorSpace::Gamma);
+        assert_eq!(canvas.row(3u32)[0 .. 4], [ 255u32; 4 ]);
+        assert_eq!(canvas.row(3u32)[5 ..], [ 0u32; 3 ]);
+
+        // Diagonally through the top left corner, every pixel on the diagonal is fully covered up to the end
+        let mut canvas: CoverageCanvas = CoverageCanvas::new(8u32, 8u32);
+        canvas.draw_line(-2.5f32, -2.5f32, 5.5f32, 5.5f32, &WHITE, ColorSpace::Gamma);
+        for i in 0u32 .. 5u32 {
+            assert_eq!(canvas.at(i, i), 255u32);
+        }
+        assert_eq!(canvas.at(5u32, 5u32), 128u32);
+        assert_eq!(canvas.total(), 255u32 * 5u32 + 128u32);
+
+        // Shallowly down across the top edge, row 0 gets what lies below the line's center
+        let mut canvas: CoverageCanvas = CoverageCanvas::new(8u32, 8u32);
+        canvas.draw_line(-4.5f32, -1.3f32, 7.5f32, 1.7f32, &WHITE, ColorSpace::Gamma);
+        assert_eq!(canvas.column(0u32)[0 .. 2], [ 115u32, 0u32 ]);
+        assert_eq!(canvas.column(1u32)[0 .. 2], [ 179u32, 0u32 ]);
+    }
+
+    #[test]
+    fn blends_in_the_given_color_space() {
+        for space in [ ColorSpace::Linear, ColorSpace::Gamma ] {
+            let mut canvas: CoverageCanvas = CoverageCanvas::new(8u32, 8u32);
+            canvas.draw_line(1.5f32, 2.5f32, 6.5f32, 4.5f32, &WHITE, space);
+            assert!(canvas.space == Some(space));
+            let mut canvas: CoverageCanvas = CoverageCanvas::new(8u32, 8u32);
+            canvas.stroke_polygon(&[ (1f32, 1f32), (6f32, 1f32), (3f32, 6f32) ], 2f32, &WHITE, space);
+            assert!(canvas.space == Some(space));
+        }
+    }
+
+    #[test]
+    fn wide_polyline_covers_its_width() {
+        let mut canvas: CoverageCanvas = CoverageCanvas::new(10u32, 10u32);
+        canvas.draw_polyline(&[ (3f32, 5f32), (8f32, 5f32) ], 4f32, &WHITE, ColorSpace::Gamma);
+        assert_eq!(canvas.column(5u32)[3 .. 7], [ 255u32; 4 ]);
+        assert_eq!(canvas.at(5u32, 1u32), 0u32);
+        assert_eq!(canvas.at(5u32, 8u32), 0u32);
+        // Round caps reach half the width past the ends
+        assert_eq!(canvas.at(0u32, 5u32), 0u32);
+        assert!(canvas.at(1u32, 5u32) > 0u32);
+    }
+
+    #[test]
+    fn circles_cover_their_area_and_ring() {
+        let mut canvas: CoverageCanvas = CoverageCanvas::new(20u32, 20u32);
+        canvas.fill_circle(10f32, 10f32, 5f32, &WHITE, ColorSpace::Gamma);
+        assert_eq!(canvas.at(10u32, 10u32), 255u32);
+        assert_eq!(canvas.at(2u32, 10u32), 0u32);
+        let area: f32 = (canvas.total() as f32) / 255f32;
+        assert!((area - std::f32::consts::PI * 25f32).abs() < 1f32);
+
+        let mut canvas: CoverageCanvas = CoverageCanvas::new(20u32, 20u32);
+        canvas.stroke_circle(10f32, 10f32, 5f32, 1f32, &WHITE, ColorSpace::Gamma);
+        assert_eq!(canvas.at(10u32, 10u32), 0u32);
+        assert!(canvas.at(14u32, 10u32) > 0u32);
+    }
+
+    #[test]
+    fn rects_cover_fractional_edges_partially() {
+        let mut canvas: CoverageCanvas = CoverageCanvas::new(8u32, 8u32);
+        canvas.fill_rect(1.5f32, 2f32, 3f32, 2f32, &WHITE, ColorSpace::Gamma);
+        assert_eq!(canvas.row(2u32), vec![ 0u32, 128u32, 255u32, 255u32, 128u32, 0u32, 0u32, 0u32 ]);
+        assert_eq!(canvas.row(3u32), canvas.row(2u32));
+        assert_eq!(canvas.row(4u32), vec![0u32; 8]);
+
+        // The outline's bands meet without blending any pixel twice
+        let mut canvas: CoverageCanvas = CoverageCanvas::new(10u32, 10u32);
+        canvas.stroke_rect(2f32, 2f32, 6f32, 6f32, 2f32, &WHITE, ColorSpace::Gamma);
+        assert!(canvas.alpha.iter().all(| a | *a == 0u32 || *a == 255u32));
+        assert_eq!(canvas.at(1u32, 1u32), 255u32);
+        assert_eq!(canvas.at(5u32, 5u32), 0u32);
+        assert_eq!(canvas.total(), 255u32 * (64u32 - 16u32));
+    }
+
+    #[test]
+    fn polygons_fill_by_the_even_odd_rule() {
+        let mut canvas: CoverageCanvas = CoverageCanvas::new(10u32, 10u32);
+        canvas.fill_polygon(&[ (2f32, 2f32), (8f32, 2f32), (8f32, 8f32), (2f32, 8f32) ], &WHITE, ColorSpace::Gamma);
+        assert_eq!(canvas.total(), 255u32 * 36u32);
+
+        // A triangle covers half of the square it spans
+        let mut canvas: CoverageCanvas = CoverageCanvas::new(10u32, 10u32);
+        canvas.fill_polygon(&[ (0f32, 0f32), (8f32, 0f32), (0f32, 8f32) ], &WHITE, ColorSpace::Gamma);
+        let area: f32 = (canvas.total() as f32) / 255f32;
+        assert!((area - 32f32).abs() < 0.5f32);
+
+        // A pentagram leaves its center, crossed twice, empty
+        let star: Vec<(f32, f32)> = (0 .. 5).map(| i | {
+            let angle: f32 = (i as f32) * std::f32::consts::PI * 4f32 / 5f32;
+            (10f32 + 9f32 * angle.sin(), 10f32 - 9f32 * angle.cos())
+        }).collect();
+        let mut canvas: CoverageCanvas = CoverageCanvas::new(20u32, 20u32);
+        canvas.fill_polygon(&star, &WHITE, ColorSpace::Gamma);
+        assert_eq!(canvas.at(10u32, 10u32), 0u32);
+        assert_eq!(canvas.at(10u32, 5u32), 255u32);
+    }
+
+}
@@ -0,0 +1,39 @@
+use std::f32::consts::PI;
+use std::path::Path;
+use crate::display::camera::Camera;
+use crate::display::canvas::{Canvas, ColorSpace, ImageCanvas, ImageCanvasError, PixelDraw, SampleMode, TextureWrap, WrapMode, RGBA8F};
+use crate::math::vector::Vector3D;
+
+// Equirectangular panorama drawn behind the scene
+pub struct Environment {
+    texture: ImageCanvas
+}
+
+impl Environment {
+
+    pub fn new(texture: ImageCanvas) -> Self {
+        return Self { texture };
+    }
+
+    pub fn from_file<P>(path: P) -> Result<Environment, ImageCanvasError> where P: AsRef<Path> {
+        return ImageCanvas::from_file(path).map(Environment::new);
+    }
+
+    // Longitude runs along U starting behind the viewer (-Z), latitude along V from +Y down to -Y
+    pub fn sample_direction(&self, dir: &Vector3D, mode: SampleMode, space: ColorSpace) -> RGBA8F {
+        let u: f32 = 0.5f32 + dir.x.atan2(dir.z) / (2f32 * PI);
+        let v: f32 = dir.y.clamp(-1f32, 1f32).acos() / PI;
+        let wrap: TextureWrap = TextureWrap::new(WrapMode::Repeat, WrapMode::ClampToEdge);
+        return self.texture.sample_uv_wrap(u, v, mode, &wrap, space);
+    }
+
+    pub fn draw(&self, camera: &Camera, target: &mut dyn PixelDraw, width: u32, height: u32, mode: SampleMode, space: ColorSpace) {
+        for y in 0 .. height {
+            for x in 0 .. width {
+                let ray: Vector3D = camera.get_ray(x as f32, y as f32);
+                target.set_pixel(x, y, &space.encode(&self.sample_direction(&ray, mode, space)));
+            }
+        }
+    }
+
+}
@@ -0,0 +1,136 @@
+use crate::display::canvas::{ColorSpace, RGBA8, RGBA8F};
+
+// Fraction of light left after passing through the full [start, end] range of exponential fog
+const FOG_EXP_CUTOFF: f32 = 1f32 / 256f32;
+
+#[derive(Copy, Clone)]
+pub enum FogMode {
+    Linear,
+    Exponential,
+    ExponentialSquared
+}
+
+pub struct Fog {
+    pub mode: FogMode,
+    pub color: RGBA8,
+    pub start: f32,
+    pub end: f32
+}
+
+impl Fog {
+
+    pub fn new(mode: FogMode, start: f32, end: f32) -> Self {
+        return Self {
+            mode,
+            color: RGBA8 { r: 192u8, g: 192u8, b: 192u8, a: 255u8 },
+            start,
+            end
+        };
+    }
+
+    // Visibility of a surface at the given view-space depth, 1 meaning unaffected and 0 meaning fully fogged.
+    // The exponential modes are scaled so that they reach the cutoff at the end distance.
+    pub fn factor(&self, depth: f32) -> f32 {
+        let range: f32 = (self.end - self.start).max(f32::EPSILON);
+        let d: f32 = (depth - self.start).max(0f32);
+        let ret: f32 = match self.mode {
+            FogMode::Linear => 1f32 - (d / range),
+            FogMode::Exponential => {
+                let density: f32 = -FOG_EXP_CUTOFF.ln() / range;
+                (-density * d).exp()
+            },
+            FogMode::ExponentialSquared => {
+                let density: f32 = (-FOG_EXP_CUTOFF.ln()).sqrt() / range;
+                (-(density * d).powi(2i32)).exp()
+            }
+        };
+        return ret.clamp(0f32, 1f32);
+    }
+
+    // Blends a working color in the given color space towards the fog color
+    pub fn apply(&self, col: &mut RGBA8F, depth: f32, space: ColorSpace) {
+        let f: f32 = self.factor(depth);
+        if f >= 1f32 { return; }
+        let mut c: RGBA8F = RGBA8::lerp(&space.decode(&self.color), col, f);
+        c.a = col.a;
+        *col = c;
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODES: [FogMode; 3] = [ FogMode::Linear, FogMode::Exponential, FogMode::ExponentialSquared ];
+
+    fn assert_near(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-5f32, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn surfaces_up_to_the_start_are_clear() {
+        for mode in MODES {
+            let fog: Fog = Fog::new(mode, 2f32, 10f32);
+            assert_eq!(fog.factor(0f32), 1f32);
+            assert_eq!(fog.factor(2f32), 1f32);
+            assert!(fog.factor(2.5f32) < 1f32);
+        }
+    }
+
+    #[test]
+    fn linear_fog_is_full_from_the_end() {
+        let fog: Fog = Fog::new(FogMode::Linear, 2f32, 10f32);
+        assert_near(fog.factor(6f32), 0.5f32);
+        assert_eq!(fog.factor(10f32), 0f32);
+        assert_eq!(fog.factor(50f32), 0f32);
+    }
+
+    #[test]
+    fn exponential_fog_reaches_the_cutoff_at_the_end() {
+        for mode in [ FogMode::Exponential, FogMode::ExponentialSquared ] {
+            let fog: Fog = Fog::new(mode, 2f32, 10f32);
+            assert_near(fog.factor(10f32), FOG_EXP_CUTOFF);
+            assert!(fog.factor(9.9f32) > FOG_EXP_CUTOFF);
+            assert!(fog.factor(10.1f32) < FOG_EXP_CUTOFF);
+            assert!(fog.factor(50f32) >= 0f32);
+        }
+        // Both reach the cutoff at the end, so squared fog, which thickens faster, is clearer before it
+        let exp: Fog = Fog::new(FogMode::Exponential, 2f32, 10f32);
+        let exp2: Fog = Fog::new(FogMode::ExponentialSquared, 2f32, 10f32);
+        assert!(exp2.factor(3f32) > exp.factor(3f32));
+        assert!(exp2.factor(9f32) > exp.factor(9f32));
+        assert!(exp2.factor(12f32) < exp.factor(12f32));
+    }
+
+    #[test]
+    fn fog_without_a_range_starts_at_full_strength() {
+        for mode in MODES {
+            let fog: Fog = Fog::new(mode, 5f32, 5f32);
+            assert_eq!(fog.factor(5f32), 1f32);
+            assert_eq!(fog.factor(5.01f32), 0f32);
+            assert!(fog.factor(100f32).is_finite());
+        }
+    }
+
+    #[test]
+    fn apply_blends_towards_the_fog_color_and_keeps_alpha() {
+        let fog: Fog = Fog::new(FogMode::Linear, 0f32, 10f32);
+        let black: RGBA8F = RGBA8F { r: 0f32, g: 0f32, b: 0f32, a: 128f32 };
+
+        let mut clear: RGBA8F = black;
+        fog.apply(&mut clear, 0f32, ColorSpace::Gamma);
+        assert_eq!((clear.r, clear.a), (0f32, 128f32));
+
+        let mut half: RGBA8F = black;
+        fog.apply(&mut half, 5f32, ColorSpace::Gamma);
+        assert_near(half.r, 96f32);
+        assert_eq!(half.a, 128f32);
+
+        let mut full: RGBA8F = black;
+        fog.apply(&mut full, 10f32, ColorSpace::Gamma);
+        assert_near(full.g, 192f32);
+        assert_eq!(full.a, 128f32);
+    }
+
+}
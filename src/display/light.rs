@@ -0,0 +1,54 @@
+use crate::math::vector::Vector3D;
+use crate::math::VectorLike;
+
+pub enum LightKind {
+    Directional(Vector3D), // direction the light travels in
+    Point(Vector3D) // position
+}
+
+pub struct Light {
+    pub kind: LightKind,
+    pub ambient: f32
+}
+
+impl Light {
+
+    pub fn directional(direction: &Vector3D) -> Self {
+        let mut dir: Vector3D = direction.copy();
+        dir.normalize();
+        return Self { kind: LightKind::Directional(dir), ambient: 0.3f32 };
+    }
+
+    pub fn point(position: &Vector3D) -> Self {
+        return Self { kind: LightKind::Point(position.copy()), ambient: 0.3f32 };
+    }
+
+    pub fn is_directional(&self) -> bool {
+        return matches!(self.kind, LightKind::Directional(_));
+    }
+
+    // Unit vector pointing from the given point towards the light
+    pub fn get_incident(&self, point: &Vector3D) -> Vector3D {
+        return match &self.kind {
+            LightKind::Directional(dir) => {
+                let mut ret: Vector3D = dir.copy();
+                ret.negate();
+                ret
+            },
+            LightKind::Point(pos) => {
+                let mut ret: Vector3D = pos.copy();
+                ret.subtract_vector(point);
+                ret.normalize();
+                ret
+            }
+        };
+    }
+
+    // Light intensity in the range [ambient, 1] for a surface point, where visibility is the
+    // fraction of the point that is not in shadow
+    pub fn shade(&self, point: &Vector3D, normal: &Vector3D, visibility: f32) -> f32 {
+        let diffuse: f32 = self.get_incident(point).dot(normal).max(0f32);
+        return self.ambient + (1f32 - self.ambient) * diffuse * visibility;
+    }
+
+}
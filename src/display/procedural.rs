@@ -0,0 +1,325 @@
+use std::f32::consts::PI;
+use gif::Frame;
+use image::ImageResult;
+use crate::display::canvas::{BlendMode, Canvas, ColorSpace, HeapCanvas, MipChain, PixelDraw, RGBA8};
+
+// 3x5 glyphs, one row per entry with the most significant of the 3 bits on the left
+const FONT_GLYPHS: [(char, [u8; 5]); 36] = [
+    ('0', [0b111, 0b101, 0b101, 0b101, 0b111]),
+    ('1', [0b010, 0b110, 0b010, 0b010, 0b111]),
+    ('2', [0b111, 0b001, 0b111, 0b100, 0b111]),
+    ('3', [0b111, 0b001, 0b111, 0b001, 0b111]),
+    ('4', [0b101, 0b101, 0b111, 0b001, 0b001]),
+    ('5', [0b111, 0b100, 0b111, 0b001, 0b111]),
+    ('6', [0b111, 0b100, 0b111, 0b101, 0b111]),
+    ('7', [0b111, 0b001, 0b001, 0b010, 0b010]),
+    ('8', [0b111, 0b101, 0b111, 0b101, 0b111]),
+    ('9', [0b111, 0b101, 0b111, 0b001, 0b111]),
+    ('A', [0b010, 0b101, 0b111, 0b101, 0b101]),
+    ('B', [0b110, 0b101, 0b110, 0b101, 0b110]),
+    ('C', [0b011, 0b100, 0b100, 0b100, 0b011]),
+    ('D', [0b110, 0b101, 0b101, 0b101, 0b110]),
+    ('E', [0b111, 0b100, 0b110, 0b100, 0b111]),
+    ('F', [0b111, 0b100, 0b110, 0b100, 0b100]),
+    ('G', [0b011, 0b100, 0b101, 0b101, 0b011]),
+    ('H', [0b101, 0b101, 0b111, 0b101, 0b101]),
+    ('I', [0b111, 0b010, 0b010, 0b010, 0b111]),
+    ('J', [0b001, 0b001, 0b001, 0b101, 0b010]),
+    ('K', [0b101, 0b101, 0b110, 0b101, 0b101]),
+    ('L', [0b100, 0b100, 0b100, 0b100, 0b111]),
+    ('M', [0b101, 0b111, 0b111, 0b101, 0b101]),
+    ('N', [0b110, 0b101, 0b101, 0b101, 0b101]),
+    ('O', [0b010, 0b101, 0b101, 0b101, 0b010]),
+    ('P', [0b110, 0b101, 0b110, 0b100, 0b100]),
+    ('Q', [0b010, 0b101, 0b101, 0b110, 0b011]),
+    ('R', [0b110, 0b101, 0b110, 0b101, 0b101]),
+    ('S', [0b011, 0b100, 0b010, 0b001, 0b110]),
+    ('T', [0b111, 0b010, 0b010, 0b010, 0b010]),
+    ('U', [0b101, 0b101, 0b101, 0b101, 0b111]),
+    ('V', [0b101, 0b101, 0b101, 0b101, 0b010]),
+    ('W', [0b101, 0b101, 0b111, 0b111, 0b101]),
+    ('X', [0b101, 0b101, 0b010, 0b101, 0b101]),
+    ('Y', [0b101, 0b101, 0b010, 0b010, 0b010]),
+    ('Z', [0b111, 0b001, 0b010, 0b100, 0b111])
+];
+
+const NOISE_OCTAVES: u32 = 4u32;
+
+pub enum Procedural {
+    Checker { cells: u32, a: RGBA8, b: RGBA8 },
+    LinearGradient { a: RGBA8, b: RGBA8 },
+    RadialGradient { inner: RGBA8, outer: RGBA8 },
+    UvGrid { cells: u32 },
+    ValueNoise { scale: u32, seed: u32 },
+    PerlinNoise { scale: u32, seed: u32 },
+    Stripes { count: u32, a: RGBA8, b: RGBA8 }
+}
+
+impl Procedural {
+
+    // Color at the given texture coordinate, each in the range [0, 1]
+    pub fn evaluate(&self, u: f32, v: f32) -> RGBA8 {
+        return match self {
+            Procedural::Checker { cells, a, b } => {
+                let cx: u32 = (u * (*cells as f32)).floor() as u32;
+                let cy: u32 = (v * (*cells as f32)).floor() as u32;
+                if (cx ^ cy) & 1u32 == 0u32 { *a } else { *b }
+            },
+            Procedural::LinearGradient { a, b } => {
+                RGBA8::lerp(&a.to_float(), &b.to_float(), u).round()
+            },
+            Procedural::RadialGradient { inner, outer } => {
+                let d: f32 = ((u - 0.5f32).powi(2i32) + (v - 0.5f32).powi(2i32)).sqrt() / 0.5f32.sqrt();
+                RGBA8::lerp(&inner.to_float(), &outer.to_float(), d.min(1f32)).round()
+            },
+            Procedural::UvGrid { cells } => {
+                let n: f32 = *cells as f32;
+                let cx: u32 = (u * n).floor() as u32;
+                let cy: u32 = (v * n).floor() as u32;
+                let shade: f32 = if (cx ^ cy) & 1u32 == 0u32 { 1f32 } else { 0.75f32 };
+                RGBA8 {
+                    r: (((cx as f32) + 0.5f32) / n * 255f32 * shade) as u8,
+                    g: (((cy as f32) + 0.5f32) / n * 255f32 * shade) as u8,
+                    b: (160f32 * shade) as u8,
+                    a: 255u8
+                }
+            },
+            Procedural::ValueNoise { scale, seed } => {
+                grayscale(fractal(u, v, *scale, *seed, value_noise))
+            },
+            Procedural::PerlinNoise { scale, seed } => {
+                grayscale(fractal(u, v, *scale, *seed, perlin_noise))
+            },
+            Procedural::Stripes { count, a, b } => {
+                // Diagonal, so that the stripes still tile when u and v both wrap
+                let t: u32 = ((u + v) * (*count as f32)).floor() as u32;
+                if t & 1u32 == 0u32 { *a } else { *b }
+            }
+        };
+    }
+
+}
+
+// A procedural texture baked into a canvas
+pub struct ProceduralCanvas {
+    source: Procedural,
+    canvas: HeapCanvas<'static>
+}
+
+impl ProceduralCanvas {
+
+    pub fn new(source: Procedural, w: u32, h: u32) -> Self {
+        let mut canvas: HeapCanvas<'static> = HeapCanvas::new(w, h);
+        for y in 0 .. h {
+            let v: f32 = ((y as f32) + 0.5f32) / (h as f32);
+            for x in 0 .. w {
+                let u: f32 = ((x as f32) + 0.5f32) / (w as f32);
+                canvas.set_pixel(x, y, &source.evaluate(u, v));
+            }
+        }
+
+        let mut ret = Self { source, canvas };
+        if let Procedural::UvGrid { cells } = ret.source {
+            ret.draw_grid(cells);
+        }
+        ret
+    }
+
+    pub fn get_source(&self) -> &Procedural {
+        &self.source
+    }
+
+    pub fn build_mipmaps(&mut self, space: ColorSpace) {
+        self.canvas.build_mipmaps(space);
+    }
+
+    fn draw_grid(&mut self, cells: u32) {
+        let w: u32 = self.canvas.get_width();
+        let h: u32 = self.canvas.get_height();
+        if cells < 1u32 { return; }
+        let cell_w: u32 = w / cells;
+        let cell_h: u32 = h / cells;
+
+        // Widened so that large cell counts can't overflow
+        let line: RGBA8 = RGBA8::white();
+        let n: u64 = cells as u64;
+        for y in 0 .. h {
+            for x in 0 .. w {
+                if ((x as u64) * n) % (w as u64) < n || ((y as u64) * n) % (h as u64) < n {
+                    self.canvas.set_pixel(x, y, &line);
+                }
+            }
+        }
+
+        // Labels read as column letter then row number, e.g. A1 for the top left cell
+        let scale: u32 = (cell_w.min(cell_h) / 16u32).max(1u32);
+        if cells > 26u32 || cell_w < 10u32 * scale || cell_h < 8u32 * scale { return; }
+        for cy in 0 .. cells {
+            for cx in 0 .. cells {
+                let label: String = format!("{}{}", (b'A' + (cx as u8)) as char, cy + 1u32);
+                self.draw_text(&label, cx * cell_w + 2u32 * scale, cy * cell_h + 2u32 * scale, scale, &RGBA8::black());
+            }
+        }
+    }
+
+    fn draw_text(&mut self, text: &str, x: u32, y: u32, scale: u32, color: &RGBA8) {
+        let w: u32 = self.canvas.get_width();
+        let h: u32 = self.canvas.get_height();
+        let mut head: u32 = x;
+        for c in text.chars() {
+            if let Some((_, rows)) = FONT_GLYPHS.iter().find(| (g, _) | *g == c) {
+                for (row, bits) in rows.iter().enumerate() {
+                    for col in 0 .. 3u32 {
+                        if (bits >> (2u32 - col)) & 1u8 == 0u8 { continue; }
+                        for sy in 0 .. scale {
+                            for sx in 0 .. scale {
+                                let px: u32 = head + col * scale + sx;
+                                let py: u32 = y + (row as u32) * scale + sy;
+                                if px < w && py < h {
+                                    self.canvas.set_pixel(px, py, color);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            head += 4u32 * scale;
+        }
+    }
+
+}
+
+impl PixelDraw for ProceduralCanvas {
+    fn set_pixel(&mut self, x: u32, y: u32, color: &RGBA8) {
+        self.canvas.set_pixel(x, y, color);
+    }
+
+    fn blend_pixel(&mut self, x: u32, y: u32, color: &RGBA8, mode: BlendMode, space: ColorSpace) {
+        self.canvas.blend_pixel(x, y, color, mode, space);
+    }
+
+    fn get_bounds(&self) -> (u32, u32) {
+        (self.get_width(), self.get_height())
+    }
+}
+
+impl Canvas for ProceduralCanvas {
+    fn get_width(&self) -> u32 {
+        self.canvas.get_width()
+    }
+
+    fn get_height(&self) -> u32 {
+        self.canvas.get_height()
+    }
+
+    fn get_pixel(&self, x: u32, y: u32) -> RGBA8 {
+        self.canvas.get_pixel(x, y)
+    }
+
+    fn fill(&mut self, color: &RGBA8) {
+        self.canvas.fill(color);
+    }
+
+    fn save(&self, path: &str) -> ImageResult<()> {
+        self.canvas.save(path)
+    }
+
+    fn as_frame(&mut self, speed: i32) -> Frame<'_> {
+        self.canvas.as_frame(speed)
+    }
+
+    fn get_mip_chain(&self) -> Option<&MipChain> {
+        self.canvas.get_mip_chain()
+    }
+}
+
+fn grayscale(value: f32) -> RGBA8 {
+    let c: u8 = (value.clamp(0f32, 1f32) * 255f32).round() as u8;
+    return RGBA8 { r: c, g: c, b: c, a: 255u8 };
+}
+
+fn hash(x: u32, y: u32, seed: u32) -> u32 {
+    let mut h: u32 = x.wrapping_mul(0x27d4eb2d) ^ y.wrapping_mul(0x165667b1) ^ seed.wrapping_mul(0x9e3779b9);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x85ebca6b);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0xc2b2ae35);
+    h ^= h >> 16;
+    h
+}
+
+fn smooth(t: f32) -> f32 {
+    t * t * t * (t * (t * 6f32 - 15f32) + 10f32)
+}
+
+// Sums octaves of a noise function, each octave wrapping at its own lattice period so that the result tiles
+fn fractal(u: f32, v: f32, scale: u32, seed: u32, noise: fn(f32, f32, u32, u32) -> f32) -> f32 {
+    let mut sum: f32 = 0f32;
+    let mut amplitude: f32 = 0.5f32;
+    let mut total: f32 = 0f32;
+    let mut period: u32 = scale.max(1u32);
+    for octave in 0 .. NOISE_OCTAVES {
+        let p: f32 = period as f32;
+        sum += noise(u * p, v * p, period, seed.wrapping_add(octave)) * amplitude;
+        total += amplitude;
+        amplitude *= 0.5f32;
+        period *= 2u32;
+    }
+    sum / total
+}
+
+fn value_noise(x: f32, y: f32, period: u32, seed: u32) -> f32 {
+    let x0: u32 = (x.floor() as u32) % period;
+    let y0: u32 = (y.floor() as u32) % period;
+    let x1: u32 = (x0 + 1u32) % period;
+    let y1: u32 = (y0 + 1u32) % period;
+    let tx: f32 = smooth(x.fract());
+    let ty: f32 = smooth(y.fract());
+
+    let corner = | cx: u32, cy: u32 | (hash(cx, cy, seed) as f32) / (u32::MAX as f32);
+    let top: f32 = corner(x0, y0) + (corner(x1, y0) - corner(x0, y0)) * tx;
+    let bottom: f32 = corner(x0, y1) + (corner(x1, y1) - corner(x0, y1)) * tx;
+    top + (bottom - top) * ty
+}
+
+fn perlin_noise(x: f32, y: f32, period: u32, seed: u32) -> f32 {
+    let x0: u32 = (x.floor() as u32) % period;
+    let y0: u32 = (y.floor() as u32) % period;
+    let x1: u32 = (x0 + 1u32) % period;
+    let y1: u32 = (y0 + 1u32) % period;
+    let fx: f32 = x.fract();
+    let fy: f32 = y.fract();
+
+    let gradient = | cx: u32, cy: u32, dx: f32, dy: f32 | {
+        let angle: f32 = ((hash(cx, cy, seed) & 0xffff) as f32) / 65536f32 * PI * 2f32;
+        angle.cos() * dx + angle.sin() * dy
+    };
+    let tx: f32 = smooth(fx);
+    let ty: f32 = smooth(fy);
+    let top: f32 = gradient(x0, y0, fx, fy) + (gradient(x1, y0, fx - 1f32, fy) - gradient(x0, y0, fx, fy)) * tx;
+    let bottom: f32 = gradient(x0, y1, fx, fy - 1f32) + (gradient(x1, y1, fx - 1f32, fy - 1f32) - gradient(x0, y1, fx, fy - 1f32)) * tx;
+
+    // Gradient noise in 2D stays within [-sqrt(0.5), sqrt(0.5)]
+    (top + (bottom - top) * ty) / (2f32 * 0.5f32.sqrt()) + 0.5f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checker_alternates_cells() {
+        let magenta: RGBA8 = RGBA8 { r: 255u8, g: 0u8, b: 255u8, a: 255u8 };
+        let checker: Procedural = Procedural::Checker { cells: 4u32, a: RGBA8::black(), b: magenta };
+        assert_eq!(checker.evaluate(0.1f32, 0.1f32).r, 0u8);
+        assert_eq!(checker.evaluate(0.3f32, 0.1f32).r, magenta.r);
+        assert_eq!(checker.evaluate(0.3f32, 0.3f32).r, 0u8);
+    }
+
+    #[test]
+    fn uv_grid_with_huge_cell_count_does_not_overflow() {
+        let canvas: ProceduralCanvas = ProceduralCanvas::new(Procedural::UvGrid { cells: 100_000_000u32 }, 64u32, 64u32);
+        assert_eq!(canvas.get_width(), 64u32);
+    }
+
+}
@@ -3,7 +3,7 @@ extern crate gif;
 
 use std::fs::File;
 use gif::{DisposalMethod, Encoder, EncodingError, Repeat};
-use crate::display::canvas::{Canvas, RGBA8};
+use crate::display::canvas::{BlendMode, Canvas, ColorSpace, RGBA8};
 
 pub struct GifRenderer<'a> {
     encoder: Encoder<&'a mut File>,
@@ -78,7 +78,8 @@ pub struct GifFrameData {
 
 pub enum GifRendererOp<'t> {
     Fill(&'t RGBA8),
-    SetPixel(u32, u32, &'t RGBA8)
+    SetPixel(u32, u32, &'t RGBA8),
+    BlendPixel(u32, u32, &'t RGBA8, BlendMode, ColorSpace)
 }
 
 impl<'a> GifRenderer<'a> {
@@ -88,6 +89,14 @@ impl<'a> GifRenderer<'a> {
         Self { encoder, canvas, frame_count, head: 0u16, delay: GifRendererDelayData::new(frame_rate) }
     }
 
+    pub fn get_width(&self) -> u32 {
+        self.canvas.get_width()
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.canvas.get_height()
+    }
+
     fn flush(&mut self) -> Result<(), EncodingError> {
         if self.head > 0 {
             let mut f = self.canvas.as_frame(10);
@@ -130,6 +139,9 @@ impl<'a> GifRenderer<'a> {
             GifRendererOp::SetPixel(x, y, col) => {
                 self.canvas.set_pixel(x, y, col);
             }
+            GifRendererOp::BlendPixel(x, y, col, mode, space) => {
+                self.canvas.blend_pixel(x, y, col, mode, space);
+            }
         }
     }
 
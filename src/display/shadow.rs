@@ -0,0 +1,178 @@
+use std::f32::consts::PI;
+use crate::display::camera::Camera;
+use crate::display::canvas::FloatCanvas;
+use crate::display::light::{Light, LightKind};
+use crate::math::quad::Quad;
+use crate::math::vector::Vector3D;
+use crate::math::VectorLike;
+
+pub struct ShadowMap {
+    camera: Camera,
+    depth: FloatCanvas,
+    pub bias: f32,
+    pub pcf_radius: u32
+}
+
+impl ShadowMap {
+
+    pub fn new(size: u32) -> Self {
+        let mut camera: Camera = Camera::new();
+        camera.set_size(size as f32, size as f32);
+        let mut depth: FloatCanvas = FloatCanvas::new(size, size);
+        depth.fill(f32::INFINITY);
+        return Self { camera, depth, bias: 0.05f32, pcf_radius: 0u32 };
+    }
+
+    pub fn get_size(&self) -> u32 {
+        return self.depth.get_width();
+    }
+
+    // Positions the light's camera so that a sphere of the given radius around the target fits the map
+    pub fn aim(&mut self, light: &Light, target: &Vector3D, radius: f32) {
+        let fov_tan: f32;
+        match &light.kind {
+            LightKind::Directional(dir) => {
+                let dist: f32 = radius * 4f32;
+                let mut pos: Vector3D = dir.copy();
+                pos.multiply_scalar(-dist);
+                pos.add_vector(target);
+                self.camera.transform.translation = pos;
+                fov_tan = (radius * 1.1f32) / dist;
+            },
+            LightKind::Point(pos) => {
+                self.camera.transform.translation = pos.copy();
+                let mut to_target: Vector3D = target.copy();
+                to_target.subtract_vector(pos);
+                let dist: f32 = to_target.norm();
+                fov_tan = if dist > radius {
+                    ((radius * 1.1f32) / dist).min(1.5f32)
+                } else {
+                    1.5f32
+                };
+            }
+        }
+        self.camera.set_fov(fov_tan * 360f32 / PI);
+        self.camera.look_at(target);
+    }
+
+    pub fn clear(&mut self) {
+        self.depth.fill(f32::INFINITY);
+    }
+
+    // Renders the depth of a world-space quad as seen from the light
+    pub fn draw_quad(&mut self, quad: &Quad) {
+        let mut projected: Quad = quad.copy();
+        if !self.camera.project_quad(&mut projected) { return; }
+
+        // Depth only needs planar interpolation, so split into triangles instead of searching for UVs
+        self.depth.draw_triangle_min(&projected.tl, &projected.tr, &projected.bl);
+        self.depth.draw_triangle_min(&projected.tr, &projected.br, &projected.bl);
+    }
+
+    // Fraction of the PCF kernel around the world-space point that is lit, in the range [0, 1]
+    pub fn visibility(&self, point: &Vector3D) -> f32 {
+        let mut projected: Vector3D = point.copy();
+        if !self.camera.project_point(&mut projected) { return 1f32; }
+
+        let size: i32 = self.get_size() as i32;
+        let cx: i32 = projected.x.round() as i32;
+        let cy: i32 = projected.y.round() as i32;
+        if cx < 0i32 || cy < 0i32 || cx >= size || cy >= size { return 1f32; }
+
+        let r: i32 = self.pcf_radius as i32;
+        let test: f32 = projected.z - self.bias;
+        let mut lit: u32 = 0u32;
+        let mut total: u32 = 0u32;
+        for dy in -r ..= r {
+            let y: i32 = (cy + dy).clamp(0i32, size - 1i32);
+            for dx in -r ..= r {
+                let x: i32 = (cx + dx).clamp(0i32, size - 1i32);
+                if test <= self.depth.get_value(x as u32, y as u32) {
+                    lit += 1u32;
+                }
+                total += 1u32;
+            }
+        }
+
+        return (lit as f32) / (total as f32);
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A level square centered on (x, y, z)
+    fn level_quad(x: f32, y: f32, z: f32, half: f32) -> Quad {
+        Quad {
+            tl: Vector3D::new(x - half, y, z + half),
+            tr: Vector3D::new(x + half, y, z + half),
+            bl: Vector3D::new(x - half, y, z - half),
+            br: Vector3D::new(x + half, y, z - half)
+        }
+    }
+
+    // A 2 unit quad at y = 0 over a ground plane at y = -1 and a smaller quad half way down, lit from straight above
+    fn quad_over_ground(pcf_radius: u32) -> ShadowMap {
+        let mut ret: ShadowMap = ShadowMap::new(256u32);
+        ret.pcf_radius = pcf_radius;
+        ret.aim(&Light::directional(&Vector3D::new(0f32, -1f32, 0f32)), &Vector3D::new(0f32, -0.5f32, 0f32), 3f32);
+        ret.draw_quad(&level_quad(0f32, 0f32, 0f32, 1f32));
+        ret.draw_quad(&level_quad(1f32, -0.5f32, 0f32, 0.5f32));
+        ret.draw_quad(&level_quad(0f32, -1f32, 0f32, 3f32));
+        ret
+    }
+
+    #[test]
+    fn quads_shadow_what_is_under_them() {
+        let sm: ShadowMap = quad_over_ground(0u32);
+        assert_eq!(sm.visibility(&Vector3D::new(0f32, -1f32, 0f32)), 0f32);
+        assert_eq!(sm.visibility(&Vector3D::new(-0.5f32, -1f32, 0.5f32)), 0f32);
+        assert_eq!(sm.visibility(&Vector3D::new(2.5f32, -1f32, 0f32)), 1f32);
+        assert_eq!(sm.visibility(&Vector3D::new(0f32, -1f32, 2f32)), 1f32);
+
+        // The smaller quad is shadowed where it is under the first, and lit where it sticks out
+        assert_eq!(sm.visibility(&Vector3D::new(0.7f32, -0.5f32, 0f32)), 0f32);
+        assert_eq!(sm.visibility(&Vector3D::new(1.3f32, -0.5f32, 0f32)), 1f32);
+    }
+
+    #[test]
+    fn bias_keeps_surfaces_from_shadowing_themselves() {
+        let mut sm: ShadowMap = quad_over_ground(0u32);
+        assert_eq!(sm.visibility(&Vector3D::new(0f32, 0f32, 0f32)), 1f32);
+        assert_eq!(sm.visibility(&Vector3D::new(2.5f32, -1f32, 0f32)), 1f32);
+        // Points just under a surface are still within the bias, but further down they are behind it
+        assert_eq!(sm.visibility(&Vector3D::new(2.5f32, -1.03f32, 0f32)), 1f32);
+        assert_eq!(sm.visibility(&Vector3D::new(2.5f32, -1.2f32, 0f32)), 0f32);
+
+        // Without a bias, a surface's own depth is enough to shadow parts of it
+        sm.bias = -0.01f32;
+        assert_eq!(sm.visibility(&Vector3D::new(2.5f32, -1f32, 0f32)), 0f32);
+    }
+
+    #[test]
+    fn pcf_softens_the_edge_of_a_shadow() {
+        let hard: ShadowMap = quad_over_ground(0u32);
+        let soft: ShadowMap = quad_over_ground(2u32);
+        // The light's camera sits 12 units above the target, so the quad's edge lands on the ground a little further out
+        let edge: Vector3D = Vector3D::new(0f32, -1f32, 12.5f32 / 11.5f32);
+        let hard_edge: f32 = hard.visibility(&edge);
+        assert!(hard_edge == 0f32 || hard_edge == 1f32);
+        let penumbra: f32 = soft.visibility(&edge);
+        assert!(penumbra > 0f32 && penumbra < 1f32, "{}", penumbra);
+
+        // Well inside and well outside the shadow, the whole kernel agrees
+        assert_eq!(soft.visibility(&Vector3D::new(0f32, -1f32, 0f32)), 0f32);
+        assert_eq!(soft.visibility(&Vector3D::new(2.5f32, -1f32, 0f32)), 1f32);
+    }
+
+    #[test]
+    fn points_outside_the_map_are_lit() {
+        let sm: ShadowMap = quad_over_ground(0u32);
+        assert_eq!(sm.visibility(&Vector3D::new(40f32, -1f32, 0f32)), 1f32);
+        // Behind the light
+        assert_eq!(sm.visibility(&Vector3D::new(0f32, 20f32, 0f32)), 1f32);
+    }
+
+}
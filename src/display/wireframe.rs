@@ -0,0 +1,121 @@
+use std::collections::HashSet;
+use crate::display::canvas::{BlendMode, ColorSpace, FloatCanvas, PixelDraw, RGBA8};
+use crate::display::draw::Draw;
+use crate::math::quad::Quad;
+use crate::math::vector::Vector3D;
+
+// How far behind the nearest surface an edge may be and still count as visible, relative to its depth
+const HIDDEN_LINE_BIAS: f32 = 0.01f32;
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum RenderMode {
+    Shaded,
+    Wireframe, // every edge, including those at the back
+    ShadedWireframe, // visible edges over the shaded model
+    HiddenLine // visible edges only
+}
+
+impl RenderMode {
+
+    pub fn draws_faces(&self) -> bool {
+        matches!(self, RenderMode::Shaded | RenderMode::ShadedWireframe)
+    }
+
+    pub fn draws_edges(&self) -> bool {
+        !matches!(self, RenderMode::Shaded)
+    }
+
+    pub fn hides_edges(&self) -> bool {
+        matches!(self, RenderMode::ShadedWireframe | RenderMode::HiddenLine)
+    }
+
+}
+
+// Draws the edges of projected quads, optionally testing them against the depth of every face
+pub struct EdgeRenderer {
+    depth: FloatCanvas,
+    space: ColorSpace, // blended in, the same as the faces under the edges
+    pub color: RGBA8,
+    pub width: f32
+}
+
+impl EdgeRenderer {
+
+    pub fn new(w: u32, h: u32, space: ColorSpace) -> Self {
+        let mut depth: FloatCanvas = FloatCanvas::new(w, h);
+        depth.fill(f32::INFINITY);
+        return Self { depth, space, color: RGBA8::white(), width: 1f32 };
+    }
+
+    pub fn clear(&mut self) {
+        self.depth.fill(f32::INFINITY);
+    }
+
+    // Adds a projected quad to the depth that edges are tested against
+    pub fn add_occluder(&mut self, quad: &Quad) {
+        self.depth.draw_triangle_min(&quad.tl, &quad.tr, &quad.bl);
+        self.depth.draw_triangle_min(&quad.tr, &quad.br, &quad.bl);
+    }
+
+    pub fn draw_edges(&self, quads: &[Quad], target: &mut dyn PixelDraw, depth_test: bool) {
+        // Neighbouring faces share edges, which would otherwise be blended twice
+        let mut drawn: HashSet<[u32; 4]> = HashSet::new();
+        for quad in quads.iter() {
+            let corners: [&Vector3D; 4] = [ &quad.tl, &quad.tr, &quad.br, &quad.bl ];
+            for i in 0 .. 4usize {
+                let a: &Vector3D = corners[i];
+                let b: &Vector3D = corners[(i + 1usize) % 4usize];
+                if a.x == b.x && a.y == b.y { continue; }
+
+                let ka: [u32; 2] = [ a.x.to_bits(), a.y.to_bits() ];
+                let kb: [u32; 2] = [ b.x.to_bits(), b.y.to_bits() ];
+                let key: [u32; 4] = if ka <= kb { [ ka[0], ka[1], kb[0], kb[1] ] } else { [ kb[0], kb[1], ka[0], ka[1] ] };
+                if !drawn.insert(key) { continue; }
+
+                let line: [(f32, f32); 2] = [ (a.x, a.y), (b.x, b.y) ];
+                if depth_test {
+                    let mut tested: DepthTestedDraw = DepthTestedDraw { target: &mut *target, depth: &self.depth, a, b };
+                    tested.draw_polyline(&line, self.width, &self.color, self.space);
+                } else {
+                    target.draw_polyline(&line, self.width, &self.color, self.space);
+                }
+            }
+        }
+    }
+
+}
+
+// Passes through only the pixels of the line from a to b that are not behind the depth canvas
+struct DepthTestedDraw<'a> {
+    target: &'a mut dyn PixelDraw,
+    depth: &'a FloatCanvas,
+    a: &'a Vector3D,
+    b: &'a Vector3D
+}
+
+impl DepthTestedDraw<'_> {
+
+    fn is_visible(&self, x: u32, y: u32) -> bool {
+        if x >= self.depth.get_width() || y >= self.depth.get_height() { return false; }
+        let dx: f32 = self.b.x - self.a.x;
+        let dy: f32 = self.b.y - self.a.y;
+        let t: f32 = (((x as f32) + 0.5f32 - self.a.x) * dx + ((y as f32) + 0.5f32 - self.a.y) * dy) / (dx * dx + dy * dy);
+        let z: f32 = self.a.z + (self.b.z - self.a.z) * t.clamp(0f32, 1f32);
+        return z <= self.depth.get_value(x, y) * (1f32 + HIDDEN_LINE_BIAS);
+    }
+
+}
+
+impl PixelDraw for DepthTestedDraw<'_> {
+    fn set_pixel(&mut self, x: u32, y: u32, col: &RGBA8) {
+        if self.is_visible(x, y) { self.target.set_pixel(x, y, col); }
+    }
+
+    fn blend_pixel(&mut self, x: u32, y: u32, col: &RGBA8, mode: BlendMode, space: ColorSpace) {
+        if self.is_visible(x, y) { self.target.blend_pixel(x, y, col, mode, space); }
+    }
+
+    fn get_bounds(&self) -> (u32, u32) {
+        self.target.get_bounds()
+    }
+}
@@ -1,6 +1,7 @@
 pub mod math;
 pub mod display;
 pub mod model;
+pub mod scene;
 mod arg;
 
 extern crate image;
@@ -11,7 +12,6 @@ extern crate tempfile;
 
 use std::alloc::{alloc, Layout};
 use std::env;
-use std::f32::consts::PI;
 use std::fs::{File, OpenOptions};
 use std::io::{Read, Write};
 use std::path::Path;
@@ -21,15 +21,36 @@ use tempfile::{tempdir, TempDir};
 use crate::arg::{Args, ArgObject};
 use crate::display::camera::Camera;
 use crate::display::canvas::{Canvas, ImageCanvas, PixelDraw, SampleMode};
-use crate::display::canvas::{HeapCanvas, RGBA8};
+use crate::display::canvas::{BlendMode, ColorSpace, HeapCanvas, TextureWrap, UvGradient, WrapMode, RGBA8, RGBA8F};
 use crate::display::render::{GifRenderer, GifRendererOp, GifRendererStep};
+use crate::display::light::Light;
+use crate::display::shadow::ShadowMap;
+use crate::display::fog::Fog;
+use crate::display::environment::Environment;
+use crate::display::procedural::{Procedural, ProceduralCanvas};
+use crate::display::animation::AnimatedCanvas;
+use crate::display::wireframe::EdgeRenderer;
+use crate::display::antialias::{downsample, MultisampleCanvas};
 use crate::math::quad::Quad;
-use crate::math::quaternion::Quaternion;
 use crate::math::vector::Vector3D;
 use crate::math::VectorLike;
-use crate::model::Model;
-use crate::model::platonic::cube;
+use crate::model::{Model, ModelFileError};
+use crate::model::platonic::{cube, dodecahedron, icosahedron, octahedron, tetrahedron};
 use crate::model::sphere::uv_sphere;
+use crate::model::torus::torus;
+use crate::model::cylinder::{capsule, cone, cylinder};
+use crate::model::plane::plane;
+use crate::model::terrain::terrain;
+use crate::model::vox::load_vox;
+use crate::model::stl::load_stl;
+use crate::model::ply::{load_ply, save_ply};
+use crate::model::obj::save_obj;
+use crate::model::gltf::load_gltf;
+use crate::scene::{Scene, SceneNode};
+
+// An orbiting object circles this many of the main object's radii out, scaled to this many of them across
+const ORBIT_DISTANCE: f32 = 1.4f32;
+const ORBIT_SIZE: f32 = 0.3f32;
 
 fn main() {
     print_title();
@@ -49,20 +70,60 @@ fn main() {
         exit(1i32);
     }
 
-    let mut model: Model;
-    if matches!(arg.object.unwrap(), ArgObject::Cube) {
-        model = cube();
-        model.transform.translate(&Vector3D::new(0f32, 0f32, 12f32));
-    } else {
-        model = uv_sphere(arg.lat, arg.lng);
-        model.transform.translate(&Vector3D::new(0f32, 0f32, 8f32));
+    let object: &ArgObject = arg.object.as_ref().unwrap();
+    let mut parts: Vec<Model> = build_object(object, &arg);
+    set_texture_wrap(&mut parts, object, &arg);
+    let mut scene: Scene = Scene::new();
+    let mut depth: f32 = if matches!(object, ArgObject::UvSphere) { 8f32 } else { 12f32 };
+    let radius: f32 = get_radius(&parts);
+    let node: &mut SceneNode = scene.add_node(object_node(parts));
+    node.spin = Vector3D::new(2f32, 1f32, 0f32);
+    if let Some(orbit) = arg.orbit.as_ref() {
+        let mut moon_parts: Vec<Model> = build_object(orbit, &arg);
+        set_texture_wrap(&mut moon_parts, orbit, &arg);
+        let moon_radius: f32 = get_radius(&moon_parts);
+        let mut moon: SceneNode = object_node(moon_parts);
+        if moon_radius > 0f32 { moon.transform.scale_scalar(ORBIT_SIZE * radius / moon_radius); }
+        moon.transform.translate(&Vector3D::new(ORBIT_DISTANCE * radius, 0f32, 0f32));
+
+        // Hung from a pivot at the object's center, which turns it around the object's y axis
+        let mut pivot: SceneNode = SceneNode::new(None);
+        pivot.spin = Vector3D::new(0f32, 1f32, 0f32);
+        pivot.add_child(moon);
+        node.add_child(pivot);
+    }
+
+    // Further back by as far as the scene reaches past the object, so that an orbit stays in frame
+    scene.update();
+    let (_, reach) = scene.get_bounds();
+    if radius > 0f32 && reach > radius {
+        depth *= reach / radius;
+    }
+
+    // Written from where the object sits before it is moved into view, with every model in one file
+    if let Some(path) = arg.export.as_ref() {
+        let models: Vec<&Model> = scene.get_models();
+        let lower: String = path.to_ascii_lowercase();
+        let saved = if lower.ends_with(".obj") { save_obj(&models, path, arg.export_bake) } else { save_ply(&models, path, arg.export_bake) };
+        if let Err(err) = saved {
+            eprintln!("{}", err);
+            exit(1i32);
+        }
+        exit(0i32);
+    }
+    scene.nodes[0usize].transform.translate(&Vector3D::new(0f32, 0f32, depth));
+
+    if let Some(blend) = arg.blend {
+        for model in scene.get_models_mut() {
+            model.material.blend = blend;
+        }
     }
 
     let output: String;
     let mut tmp_dir: Option<TempDir> = None;
     let mut tmp: bool = false;
     if arg.output.is_some() {
-        output = arg.output.unwrap();
+        output = arg.output.take().unwrap();
     } else {
         let res = tempdir();
         if res.is_err() {
@@ -76,30 +137,82 @@ fn main() {
         tmp = true;
     }
 
-    if arg.texture.is_some() {
-        let from_file = ImageCanvas::from_file(arg.texture.unwrap());
+    if let (Some(fog), Some(color)) = (arg.fog.as_mut(), arg.fog_color) {
+        fog.color = color;
+    }
+
+    let mut environment: Option<Environment> = None;
+    if let Some(path) = arg.environment.take() {
+        match Environment::from_file(path) {
+            Ok(env) => environment = Some(env),
+            Err(err) => {
+                eprintln!("{}", err);
+                exit(1i32);
+            }
+        }
+    }
+
+    let texture_path: Option<String> = arg.texture.take();
+    // Textures that came with the models are only used when none is given, and otherwise each model keeps its own
+    if texture_path.is_some() || arg.procedural.is_some() {
+        for model in scene.get_models_mut() {
+            model.material.texture = None;
+        }
+    } else if arg.interpolation.uses_mipmaps() {
+        for model in scene.get_models_mut() {
+            if let Some(texture) = model.material.texture.as_mut() {
+                texture.build_mipmaps(arg.color_space);
+            }
+        }
+    }
+
+    // A '#' only marks a numbered sequence when no file has that exact name
+    let sequence: bool = texture_path.as_ref().is_some_and(| p | p.contains('#') && !Path::new(p).exists());
+    let animated: bool = sequence || texture_path.as_ref().is_some_and(| p | p.to_ascii_lowercase().ends_with(".gif"));
+    if animated {
+        let path: String = texture_path.unwrap();
+        let from_file = if sequence {
+            AnimatedCanvas::from_sequence(&path, 1f32 / (arg.texture_fps as f32))
+        } else {
+            AnimatedCanvas::from_gif(&path)
+        };
+        if from_file.is_err() {
+            eprintln!("{}", from_file.err().unwrap());
+            exit(1i32);
+        }
+        let mut texture: AnimatedCanvas = from_file.ok().unwrap();
+        if arg.interpolation.uses_mipmaps() {
+            texture.build_mipmaps(arg.color_space);
+        }
+        eprintln!("\x1B[s");
+        render_spinning(&mut scene, &mut texture, environment.as_ref(), output.clone(), &arg);
+    } else if let Some(path) = texture_path {
+        let from_file = ImageCanvas::from_file(path);
         if from_file.is_err() {
             eprintln!("{}", from_file.unwrap_err());
             exit(1i32);
         }
+        let mut texture: ImageCanvas = from_file.unwrap();
+        if arg.interpolation.uses_mipmaps() {
+            texture.build_mipmaps(arg.color_space);
+        }
         eprintln!("\x1B[s");
-        render_spinning(&mut model, &from_file.unwrap(), output.clone(), arg.resolution, arg.interpolation);
+        render_spinning(&mut scene, &mut texture, environment.as_ref(), output.clone(), &arg);
     } else {
-        let mut hc = HeapCanvas::new(4u32, 4u32);
-        for y in 0 .. 4u32 {
-            let mut color: bool = (y % 2u32) == 1u32;
-            for x in 0 .. 4u32 {
-                let rgb = if color {
-                    RGBA8 { r: 255, g: 0, b: 255, a: 255 }
-                } else {
-                    RGBA8::black()
-                };
-                hc.set_pixel(x, y, &rgb);
-                color = !color;
-            }
+        // Without a texture, a 4x4 checker is drawn one texel per cell, with black in the top left corner
+        let mut texture: ProceduralCanvas = match arg.procedural.take() {
+            Some(source) => ProceduralCanvas::new(source, 512u32, 512u32),
+            None => ProceduralCanvas::new(Procedural::Checker {
+                cells: 4u32,
+                a: RGBA8::black(),
+                b: RGBA8 { r: 255, g: 0, b: 255, a: 255 }
+            }, 4u32, 4u32)
+        };
+        if arg.interpolation.uses_mipmaps() {
+            texture.build_mipmaps(arg.color_space);
         }
         eprintln!("\x1B[s");
-        render_spinning(&mut model, &hc, output.clone(), arg.resolution, arg.interpolation);
+        render_spinning(&mut scene, &mut texture, environment.as_ref(), output.clone(), &arg);
     }
 
     if tmp {
@@ -158,20 +271,76 @@ fn print_help() {
 
     eprintln!("{}", head.bold().paint(" USAGE "));
     eprintln!("{}", name.bold().paint("blot <object> [--args]"));
-    eprintln!("{} {} {}", name.paint("object"), sep.paint("::"), detail.paint("cube or uv_sphere"));
-    eprintln!("{} {} {}", name.paint("--texture"), sep.paint("::"), detail.paint("path to input texture"));
+    eprintln!("{} {} {}", name.paint("object"), sep.paint("::"), detail.paint("cube, tetrahedron, octahedron, dodecahedron, icosahedron, uv_sphere, torus, cylinder, cone, plane, capsule, terrain or a path to a .vox, .stl, .ply, .gltf or .glb model"));
+    eprintln!("{} {} {}", name.paint("--texture"), sep.paint("::"), detail.paint("path to input texture (animated GIFs play), or proc:<checker|gradient|radial|uv|noise|perlin|stripes>[:params]"));
+    eprintln!("{} {} {}", name.paint("--texture-fps"), sep.paint("::"), detail.paint("frame rate of a numbered texture sequence such as frame_###.png"));
     eprintln!("{} {} {}", name.paint("--out"), sep.paint("::"), detail.paint("path to output gif file"));
     eprintln!("{} {} {}", name.paint("--resolution"), sep.paint("::"), detail.paint("gif resolution (1 - 65535)"));
-    eprintln!("{} {} {}", name.paint("--interpolation"), sep.paint("::"), detail.paint("nearest, bilinear or bicubic"));
-    eprintln!("{} {} {}", name.paint("--lat"), sep.paint("::"), detail.paint("latitude steps (3 - 65535) for uv_sphere"));
-    eprintln!("{} {} {}", name.paint("--lng"), sep.paint("::"), detail.paint("longitude steps (3 - 65535) for uv_sphere"));
+    eprintln!("{} {} {}", name.paint("--interpolation"), sep.paint("::"), detail.paint("nearest, bilinear, bicubic, mip, trilinear or anisotropic"));
+    eprintln!("{} {} {}", name.paint("--lat"), sep.paint("::"), detail.paint("latitude steps (3 - 65535) for uv_sphere, or segments around other curved objects"));
+    eprintln!("{} {} {}", name.paint("--lng"), sep.paint("::"), detail.paint("longitude steps (3 - 65535) for uv_sphere, or rings along other curved objects"));
+    eprintln!("{} {} {}", name.paint("--light"), sep.paint("::"), detail.paint("directional:x,y,z or point:x,y,z"));
+    eprintln!("{} {} {}", name.paint("--shadows"), sep.paint("::"), detail.paint("off, hard or soft"));
+    eprintln!("{} {} {}", name.paint("--ground"), sep.paint("::"), detail.paint("draw a ground plane this far below the object"));
+    eprintln!("{} {} {}", name.paint("--fog"), sep.paint("::"), detail.paint("linear, exp or exp2 as mode:start:end"));
+    eprintln!("{} {} {}", name.paint("--fog-color"), sep.paint("::"), detail.paint("fog color as r,g,b"));
+    eprintln!("{} {} {}", name.paint("--environment"), sep.paint("::"), detail.paint("path to equirectangular background image"));
+    eprintln!("{} {} {}", name.paint("--wrap"), sep.paint("::"), detail.paint("repeat, mirror, clamp or border, optionally as u,v (overrides the samplers of glTF textures)"));
+    eprintln!("{} {} {}", name.paint("--border-color"), sep.paint("::"), detail.paint("border wrap color as r,g,b,a"));
+    eprintln!("{} {} {}", name.paint("--color-space"), sep.paint("::"), detail.paint("gamma (default) to work on sRGB bytes, or linear to decode them for filtering, blending and lighting"));
+    eprintln!("{} {} {}", name.paint("--render"), sep.paint("::"), detail.paint("shaded, wireframe, overlay (wireframe over shaded) or hidden (hidden-line)"));
+    eprintln!("{} {} {}", name.paint("--wire-color"), sep.paint("::"), detail.paint("edge color as r,g,b,a"));
+    eprintln!("{} {} {}", name.paint("--aa"), sep.paint("::"), detail.paint("off, ssaa2x2, ssaa4x4 or ssaa8x8 (grid of samples per pixel), or msaa2, msaa4 or msaa8 (samples per pixel)"));
+    eprintln!("{} {} {}", name.paint("--cube-layout"), sep.paint("::"), detail.paint("same (default), cross, 3x2 or 6x1 texture atlas for the cube's faces"));
+    eprintln!("{} {} {}", name.paint("--heightmap"), sep.paint("::"), detail.paint("path to grayscale image that raises the terrain where it is bright"));
+    eprintln!("{} {} {}", name.paint("--height-scale"), sep.paint("::"), detail.paint("terrain height where the heightmap is white (default 0.5)"));
+    eprintln!("{} {} {}", name.paint("--terrain-grid"), sep.paint("::"), detail.paint("terrain quads across as n or cols,rows (default 64)"));
+    eprintln!("{} {} {}", name.paint("--export"), sep.paint("::"), detail.paint("write the object to an .obj or .ply file instead of rendering it"));
+    eprintln!("{} {} {}", name.paint("--export-bake"), sep.paint("::"), detail.paint("as --export, with node transforms baked into the vertices so that an --orbit object is written in place"));
+    eprintln!("{} {} {}", name.paint("--orbit"), sep.paint("::"), detail.paint("another object to circle the first, as for object"));
+    eprintln!("{} {} {}", name.paint("--blend"), sep.paint("::"), detail.paint("replace, alpha_test[:threshold], over, add, multiply or screen"));
     eprintln!();
 }
 
-fn render_spinning<P: AsRef<Path>>(model: &mut Model, texture: &dyn Canvas, output: P, size: u16, int: SampleMode) {
+fn render_spinning<P: AsRef<Path>>(scene: &mut Scene, texture: &mut dyn Canvas, environment: Option<&Environment>, output: P, arg: &Args) {
+    let size: u16 = arg.resolution;
+    let int: SampleMode = arg.interpolation;
+    let scale: u32 = arg.aa.get_scale();
+    let render_size: u32 = (size as u32) * scale;
+    let sample_offsets: Option<Vec<(f32, f32)>> = arg.aa.get_sample_offsets();
+
     let mut camera: Camera = Camera::new();
     camera.set_fov(22.5f32);
-    camera.set_size(size as f32, size as f32);
+    camera.set_size(render_size as f32, render_size as f32);
+
+    let default_light: Light = Light::directional(&Vector3D::new(-0.5f32, -1f32, 0.75f32));
+    let light: Option<&Light> = if arg.light.is_some() {
+        arg.light.as_ref()
+    } else if arg.shadows.is_some() {
+        Some(&default_light)
+    } else {
+        None
+    };
+
+    let mut shadow_map: Option<ShadowMap> = None;
+    if let (Some(_), Some(pcf_radius)) = (light, arg.shadows) {
+        let mut sm: ShadowMap = ShadowMap::new(512u32);
+        sm.pcf_radius = pcf_radius;
+        shadow_map = Some(sm);
+    }
+    scene.update();
+    let (center, _) = scene.get_bounds();
+    let ground: Vec<Quad> = arg.ground.map_or(Vec::new(), | offset | ground_quads(&center, offset));
+    let fog: Option<&Fog> = arg.fog.as_ref();
+    let space: ColorSpace = arg.color_space;
+    let ground_color: RGBA8F = space.decode(&RGBA8 { r: 160, g: 160, b: 160, a: 255 });
+    let mut edges: Option<EdgeRenderer> = None;
+    if arg.render_mode.draws_edges() {
+        let mut er: EdgeRenderer = EdgeRenderer::new(render_size, render_size, space);
+        er.color = arg.wire_color;
+        er.width = scale as f32;
+        edges = Some(er);
+    }
 
     let open = OpenOptions::new().write(true).create(true).open(output);
     if open.is_err() {
@@ -179,9 +348,13 @@ fn render_spinning<P: AsRef<Path>>(model: &mut Model, texture: &dyn Canvas, outp
         exit(1i32);
     }
     let mut out_file = open.unwrap();
+    // Frames are drawn into one of these first when anti-aliasing, then resolved into the renderer
+    let mut supersampled: Option<HeapCanvas> = if scale > 1u32 { Some(HeapCanvas::new(render_size, render_size)) } else { None };
+    let mut multisampled: Option<MultisampleCanvas> = sample_offsets.as_ref().map(| o | MultisampleCanvas::new(size as u32, size as u32, o.len() as u32));
     let mut canvas: HeapCanvas = HeapCanvas::new(size as u32, size as u32);
     let mut renderer: GifRenderer = GifRenderer::new(&mut out_file, &mut canvas, 24u16, 48u16);
 
+    let mut time: f32 = 0f32;
     let mut step: GifRendererStep;
     loop {
         step = renderer.step();
@@ -196,62 +369,150 @@ fn render_spinning<P: AsRef<Path>>(model: &mut Model, texture: &dyn Canvas, outp
                 eprint!("\x1B[u\x1B[1G");
                 print_progress(fd.index, fd.total);
 
-                let pc: f32 = (fd.index as f32) / (fd.total as f32);
-                model.transform.rotation = Quaternion::from_euler(pc * PI * 4f32, pc * PI * 2f32, 0f32);
+                texture.set_time(time);
+                time += fd.delta;
 
-                struct FaceData { quad: Quad, index: usize }
-                let mut faces: Vec<FaceData> = Vec::with_capacity(model.get_face_count());
-                for idx in 0 .. model.get_face_count() {
-                    let quad: Quad = model.get_face(idx);
-                    faces.push(FaceData { quad, index: idx });
+                let pc: f32 = (fd.index as f32) / (fd.total as f32);
+                // Children are carried around with the nodes that spin, so they orbit them
+                scene.spin(pc);
+                scene.update();
+                let models: Vec<&Model> = scene.get_models();
+                let (center, radius) = scene.get_bounds();
+
+                // Each vertex is transformed and projected once, however many faces share it
+                let world_vertices: Vec<Vec<Vector3D>> = models.iter().map(| m | m.get_world_vertices()).collect();
+                let mut screen_vertices: Vec<Vec<Vector3D>> = world_vertices.iter().map(| vs | vs.iter().map(| v | v.copy()).collect()).collect();
+                let on_screen: Vec<Vec<bool>> = screen_vertices.iter_mut().map(| vs | vs.iter_mut().map(| v | camera.project_point(v)).collect()).collect();
+
+                // Faces of every model are sorted together, so that they overlap properly
+                struct FaceData { quad: Quad, model: usize, index: usize }
+                let mut faces: Vec<FaceData> = Vec::with_capacity(models.iter().map(| m | m.get_face_count()).sum());
+                for (m, model) in models.iter().enumerate() {
+                    for idx in 0 .. model.get_face_count() {
+                        let quad: Quad = model.get_face_from(idx, &world_vertices[m]);
+                        faces.push(FaceData { quad, model: m, index: idx });
+                    }
                 }
                 faces.sort_unstable_by(| a, b | a.quad.cmp(&b.quad));
 
-                renderer.write(GifRendererOp::Fill(&RGBA8 { r: 0, g: 0, b: 0, a: 0 }));
-                let to_render: usize = ((faces.len() as f32) / 2f32).ceil() as usize;
+                if let Some(sm) = shadow_map.as_mut() {
+                    sm.clear();
+                    sm.aim(light.unwrap(), &center, radius);
+                    for face_data in faces.iter() {
+                        sm.draw_quad(&face_data.quad);
+                    }
+                }
+
+                let transparent: RGBA8 = RGBA8 { r: 0, g: 0, b: 0, a: 0 };
+                if let Some(c) = supersampled.as_mut() {
+                    c.fill(&transparent);
+                } else if let Some(ms) = multisampled.as_mut() {
+                    ms.fill(&transparent);
+                } else {
+                    renderer.write(GifRendererOp::Fill(&transparent));
+                }
+                let target: &mut dyn PixelDraw = if let Some(c) = supersampled.as_mut() {
+                    c
+                } else if let Some(ms) = multisampled.as_mut() {
+                    ms
+                } else {
+                    &mut renderer
+                };
+
+                if let Some(env) = environment {
+                    env.draw(&camera, target, render_size, render_size, int, space);
+                }
+
+                let up: Vector3D = Vector3D::new(0f32, 1f32, 0f32);
+                for world in ground.iter() {
+                    let mut face: Quad = world.copy();
+                    // Tiles reaching behind the camera would be left partly in world space
+                    if !camera.project_quad(&mut face) { continue; }
+                    rasterize_into(&face, render_size, sample_offsets.as_deref(), target, BlendMode::Replace, space, | u, v | {
+                        let point: Vector3D = world.get_point(u, v);
+                        let visibility: f32 = shadow_map.as_ref().map_or(1f32, | sm | sm.visibility(&point));
+                        let mut col: RGBA8F = ground_color;
+                        col.scale_rgb(light.map_or(1f32, | l | l.shade(&point, &up, visibility)));
+                        if let Some(f) = fog { f.apply(&mut col, face.get_point(u, v).z, space); }
+                        space.encode(&col)
+                    });
+                }
+
+                let mut projected: Vec<Quad> = Vec::new();
+                if let Some(er) = edges.as_mut() {
+                    er.clear();
+                    for face_data in faces.iter() {
+                        let m: usize = face_data.model;
+                        if !models[m].get_face_vertices(face_data.index).iter().all(| i | on_screen[m][*i]) { continue; }
+                        let face: Quad = models[m].get_face_from(face_data.index, &screen_vertices[m]);
+                        if arg.render_mode.hides_edges() { er.add_occluder(&face); }
+                        projected.push(face);
+                    }
+                }
+
+                let to_render: usize = if arg.render_mode.draws_faces() { faces.len() } else { 0usize };
 
                 for idx in (0 .. to_render).rev() {
-                    let face_data: &mut FaceData = &mut faces[idx];
+                    let face_data: &FaceData = &faces[idx];
+                    let model: &Model = models[face_data.model];
                     let index: usize = face_data.index;
-                    let face: &mut Quad = &mut face_data.quad;
+                    let world: &Quad = &face_data.quad;
 
-                    let mut normal: Vector3D = face.get_center();
-                    normal.subtract_vector(&model.transform.translation);
-                    normal.normalize();
+                    // Faces wind so that their normal points outward, and those pointing away can't be seen
+                    let mut view: Vector3D = world.get_center();
+                    view.subtract_vector(&camera.transform.translation);
+                    if world.get_normal().dot(&view) >= 0f32 { continue; }
+
+                    let flat_normal: Vector3D = world.get_normal();
                     let mut light_ray: Vector3D = camera.transform.translation.copy();
                     light_ray.subtract_vector(&model.transform.translation);
                     light_ray.normalize();
-                    let light: f32 = light_ray.dot(&normal).powi(2i32) * 0.5f32 + 0.5f32;
-
-                    camera.project_quad(face);
-
-                    let mut min_x: u32 = 0;
-                    let mut min_y: u32 = 0;
-                    let mut max_x: u32 = 0;
-                    let mut max_y: u32 = 0;
-                    face.extract_int_bounds(&mut min_x, &mut min_y, &mut max_x, &mut max_y);
-
-                    let mut u: f32 = 0f32;
-                    let mut v: f32 = 0f32;
-
-                    for x in min_x ..= max_x {
-                        if x >= (size as u32) { continue; }
-                        for y in min_y ..= max_y {
-                            if y >= (size as u32) { continue; }
-
-                            if !face.get_uv(&Vector3D { x: x as f32, y: y as f32, z: 0f32 }, &mut u, &mut v) {
-                                continue;
-                            }
-                            model.remap_face_uv(index, &mut u, &mut v);
-                            let mut col: RGBA8 = texture.sample_uv(u, v, int);
-                            if light < 1f32 {
-                                col.r = ((col.r as f32) * light).floor() as u8;
-                                col.g = ((col.g as f32) * light).floor() as u8;
-                                col.b = ((col.b as f32) * light).floor() as u8;
-                            }
-                            renderer.set_pixel(x, y, &col);
+
+                    let face: Quad = model.get_face_from(index, &screen_vertices[face_data.model]);
+                    let model_texture: &dyn Canvas = match model.material.texture.as_ref() {
+                        Some(t) => t,
+                        None => &*texture
+                    };
+                    let model_wrap: TextureWrap = model.material.wrap.unwrap_or_else(TextureWrap::clamp);
+
+                    rasterize_into(&face, render_size, sample_offsets.as_deref(), target, model.material.blend, space, | mut u, mut v | {
+                        let normal: Vector3D = model.get_face_normal(index, u, v).unwrap_or_else(|| flat_normal.copy());
+                        let shade: f32 = match light {
+                            Some(l) => {
+                                let point: Vector3D = world.get_point(u, v);
+                                let visibility: f32 = shadow_map.as_ref().map_or(1f32, | sm | sm.visibility(&point));
+                                l.shade(&point, &normal, visibility)
+                            },
+                            None => light_ray.dot(&normal).powi(2i32) * 0.5f32 + 0.5f32
+                        };
+                        let depth: f32 = face.get_point(u, v).z;
+                        let grad: UvGradient = UvGradient::from_partials(
+                            &face.get_partials(u, v),
+                            &model.get_face_uv_partials(index, u, v)
+                        );
+                        let tint: Option<RGBA8F> = model.get_face_color(index, u, v, space);
+                        model.remap_face_uv(index, &mut u, &mut v);
+                        let mut col: RGBA8F = model_texture.sample_uv_grad(u, v, &grad, int, &model_wrap, space);
+                        if let Some(t) = tint {
+                            col.r *= t.r / 255f32;
+                            col.g *= t.g / 255f32;
+                            col.b *= t.b / 255f32;
+                            col.a *= t.a / 255f32;
                         }
-                    }
+                        col.scale_rgb(shade);
+                        if let Some(f) = fog { f.apply(&mut col, depth, space); }
+                        space.encode(&col)
+                    });
+                }
+
+                if let Some(er) = edges.as_ref() {
+                    er.draw_edges(&projected, target, arg.render_mode.hides_edges());
+                }
+
+                if let Some(c) = supersampled.as_ref() {
+                    downsample(c, &mut renderer, scale, space);
+                } else if let Some(ms) = multisampled.as_ref() {
+                    ms.resolve(&mut renderer, space);
                 }
             }
         }
@@ -264,6 +525,133 @@ fn render_spinning<P: AsRef<Path>>(model: &mut Model, texture: &dyn Canvas, outp
     eprintln!();
 }
 
+// Rasterizes a projected quad with the color returned for each UV, testing coverage per sample when multisampling
+fn rasterize_into(face: &Quad, size: u32, sample_offsets: Option<&[(f32, f32)]>, target: &mut dyn PixelDraw, blend: BlendMode, space: ColorSpace, mut shade: impl FnMut(f32, f32) -> RGBA8) {
+    match sample_offsets {
+        Some(offsets) => face.rasterize_samples(size, size, offsets, | x, y, u, v, mask | {
+            target.blend_samples(x, y, mask, &shade(u, v), blend, space);
+        }),
+        None => face.rasterize(size, size, | x, y, u, v | {
+            target.blend_pixel(x, y, &shade(u, v), blend, space);
+        })
+    }
+}
+
+// Builds or loads an object, centered on its own origin. Files can hold several parts that each need their own texture.
+fn build_object(object: &ArgObject, arg: &Args) -> Vec<Model> {
+    let model: Model = match object {
+        ArgObject::UvSphere => uv_sphere(arg.lat, arg.lng),
+        ArgObject::File(path) => {
+            let from_file = load_model(path);
+            if from_file.is_err() {
+                eprintln!("{}", from_file.err().unwrap());
+                exit(1i32);
+            }
+            return from_file.ok().unwrap();
+        },
+        ArgObject::Terrain => {
+            let from_file = ImageCanvas::from_file(arg.heightmap.as_ref().unwrap());
+            if from_file.is_err() {
+                eprintln!("{}", from_file.unwrap_err());
+                exit(1i32);
+            }
+            let (cols, rows) = arg.terrain_grid;
+            terrain(&from_file.unwrap(), 3f32, arg.height_scale, cols, rows)
+        },
+        // Sized to reach about as far from their center as the cube does
+        ArgObject::Tetrahedron => tetrahedron(),
+        ArgObject::Octahedron => octahedron(),
+        ArgObject::Dodecahedron => dodecahedron(),
+        ArgObject::Icosahedron => icosahedron(),
+        ArgObject::Torus => torus(1.2f32, 0.5f32, arg.lat, arg.lng),
+        ArgObject::Cylinder => cylinder(1f32, 2f32, arg.lat, arg.lng),
+        ArgObject::Cone => cone(1f32, 2f32, arg.lat, arg.lng),
+        ArgObject::Plane => plane(2.4f32, 2.4f32, arg.lat, arg.lng),
+        ArgObject::Capsule => capsule(0.6f32, 1.6f32, arg.lat, arg.lng),
+        ArgObject::Cube => cube(arg.cube_layout)
+    };
+    return vec![model];
+}
+
+fn load_model(path: &str) -> Result<Vec<Model>, ModelFileError> {
+    let lower: String = path.to_ascii_lowercase();
+    if lower.ends_with(".stl") {
+        return load_stl(path).map(| m | vec![m]);
+    }
+    if lower.ends_with(".ply") {
+        return load_ply(path).map(| m | vec![m]);
+    }
+    if lower.ends_with(".gltf") || lower.ends_with(".glb") {
+        return load_gltf(path);
+    }
+    return load_vox(path).map(| m | vec![m]);
+}
+
+// A node for the parts of an object, with a child for each when there are several
+fn object_node(mut parts: Vec<Model>) -> SceneNode {
+    if parts.len() == 1usize {
+        return SceneNode::new(parts.pop());
+    }
+    let mut ret: SceneNode = SceneNode::new(None);
+    for part in parts {
+        ret.add_child(SceneNode::new(Some(part)));
+    }
+    return ret;
+}
+
+// Distance from the shared origin of an object's parts to their furthest vertex
+fn get_radius(parts: &[Model]) -> f32 {
+    return parts.iter().map(| m | m.get_radius()).fold(0f32, f32::max);
+}
+
+// Wraps each part's texture as its file says, or else as suits the object's shape, unless --wrap is given
+fn set_texture_wrap(parts: &mut [Model], object: &ArgObject, arg: &Args) {
+    for part in parts.iter_mut() {
+        if arg.wrap.is_some() || part.material.wrap.is_none() {
+            part.material.wrap = Some(get_texture_wrap(object, arg));
+        }
+    }
+}
+
+fn get_texture_wrap(object: &ArgObject, arg: &Args) -> TextureWrap {
+    let mut ret: TextureWrap = match arg.wrap {
+        Some(wrap) => wrap,
+        None => match object {
+            // Repeat around the meridian so that filtering doesn't leave a seam
+            ArgObject::UvSphere | ArgObject::Cylinder | ArgObject::Cone | ArgObject::Capsule => TextureWrap::new(WrapMode::Repeat, WrapMode::ClampToEdge),
+            ArgObject::Torus => TextureWrap::new(WrapMode::Repeat, WrapMode::Repeat),
+            _ => TextureWrap::clamp()
+        }
+    };
+    ret.border = arg.border_color;
+    ret
+}
+
+// The ground is split into tiles so that each quad stays small on screen
+fn ground_quads(center: &Vector3D, offset: f32) -> Vec<Quad> {
+    let extent: f32 = 6f32;
+    let tiles: u32 = 8u32;
+    let tile: f32 = (extent * 2f32) / (tiles as f32);
+    let y: f32 = center.y - offset;
+
+    let mut ret: Vec<Quad> = Vec::with_capacity((tiles * tiles) as usize);
+    for tz in 0 .. tiles {
+        let z1: f32 = center.z + extent - (tz as f32) * tile;
+        let z2: f32 = z1 - tile;
+        for tx in 0 .. tiles {
+            let x1: f32 = center.x - extent + (tx as f32) * tile;
+            let x2: f32 = x1 + tile;
+            ret.push(Quad {
+                tl: Vector3D::new(x1, y, z1),
+                tr: Vector3D::new(x2, y, z1),
+                bl: Vector3D::new(x1, y, z2),
+                br: Vector3D::new(x2, y, z2)
+            });
+        }
+    }
+    ret
+}
+
 fn print_progress(cur: u16, total: u16) {
     let pc: f32 = (cur as f32) / (total as f32);
 
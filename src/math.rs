@@ -137,8 +137,9 @@ pub trait VectorLike {
     }
 
     fn divide_vector(&mut self, other: &dyn VectorLike) where Self: Sized {
+        // Components divided by zero are left alone
         self.unary(other, | a, b |
-            if (b - a).abs() <= f32::EPSILON {
+            if b.abs() <= f32::EPSILON {
                 a
             } else {
                 a / b
@@ -177,3 +178,17 @@ pub trait VectorLike {
         vec_update(self, | _, v | -v);
     }
 }
+
+// Checks shared by the tests of anything that works with vectors
+#[cfg(test)]
+pub(crate) mod testing {
+    use crate::math::vector::Vector3D;
+    use crate::math::VectorLike;
+
+    pub(crate) fn assert_close(a: &Vector3D, b: &Vector3D) {
+        let mut diff: Vector3D = a.copy();
+        diff.subtract_vector(b);
+        assert!(diff.norm() < 1e-4f32, "({}, {}, {}) != ({}, {}, {})", a.x, a.y, a.z, b.x, b.y, b.z);
+    }
+
+}
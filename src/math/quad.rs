@@ -54,6 +54,40 @@ impl Quad {
         return ret;
     }
 
+    pub fn get_point(&self, u: f32, v: f32) -> Vector3D {
+        return Vector3D::lerp(
+            &Vector3D::lerp(&self.tl, &self.tr, u),
+            &Vector3D::lerp(&self.bl, &self.br, u),
+            v
+        );
+    }
+
+    // Partial derivatives of get_point with respect to u and v
+    pub fn get_partials(&self, u: f32, v: f32) -> [Vector3D; 2] {
+        let mut top: Vector3D = self.tr.copy();
+        top.subtract_vector(&self.tl);
+        let mut bottom: Vector3D = self.br.copy();
+        bottom.subtract_vector(&self.bl);
+        let mut left: Vector3D = self.bl.copy();
+        left.subtract_vector(&self.tl);
+        let mut right: Vector3D = self.br.copy();
+        right.subtract_vector(&self.tr);
+        return [
+            Vector3D::lerp(&top, &bottom, v),
+            Vector3D::lerp(&left, &right, u)
+        ];
+    }
+
+    pub fn get_normal(&self) -> Vector3D {
+        let mut a: Vector3D = self.br.copy();
+        a.subtract_vector(&self.tl);
+        let mut b: Vector3D = self.bl.copy();
+        b.subtract_vector(&self.tr);
+        let mut ret: Vector3D = a.cross(&b);
+        ret.normalize();
+        return ret;
+    }
+
     pub fn get_bases(&self) -> [Line; 2] {
         return [
             Line::new(&self.tl, &self.tr),
@@ -81,6 +115,122 @@ impl Quad {
         }
     }
 
+    pub fn rasterize(&self, width: u32, height: u32, mut f: impl FnMut(u32, u32, f32, f32)) {
+        let mut min_x: u32 = 0;
+        let mut min_y: u32 = 0;
+        let mut max_x: u32 = 0;
+        let mut max_y: u32 = 0;
+        self.extract_int_bounds(&mut min_x, &mut min_y, &mut max_x, &mut max_y);
+
+        let mut u: f32 = 0f32;
+        let mut v: f32 = 0f32;
+
+        if width < 1u32 || height < 1u32 { return; }
+        max_x = max_x.min(width - 1u32);
+        max_y = max_y.min(height - 1u32);
+
+        for x in min_x ..= max_x {
+            for y in min_y ..= max_y {
+                // The search can miss pixels of quads with collapsed corners, such as triangles, so fall back to the closed form
+                if !self.get_uv(&Vector3D { x: x as f32, y: y as f32, z: 0f32 }, &mut u, &mut v) {
+                    if !self.contains_point(x as f32, y as f32) { continue; }
+                    match self.get_uv_inverse(x as f32, y as f32) {
+                        Some((iu, iv)) if iu.is_finite() && iv.is_finite() => {
+                            u = iu.clamp(0f32, 1f32);
+                            v = iv.clamp(0f32, 1f32);
+                        },
+                        _ => continue
+                    }
+                }
+                f(x, y, u, v);
+            }
+        }
+    }
+
+    // Like rasterize, but tests coverage exactly at several sample offsets around each pixel center, passing a mask
+    // with a bit set for each covered sample. The UV is still found once per pixel, at the center when it is covered.
+    pub fn rasterize_samples(&self, width: u32, height: u32, offsets: &[(f32, f32)], mut f: impl FnMut(u32, u32, f32, f32, u32)) {
+        let mut min_x: u32 = 0;
+        let mut min_y: u32 = 0;
+        let mut max_x: u32 = 0;
+        let mut max_y: u32 = 0;
+        self.extract_int_bounds(&mut min_x, &mut min_y, &mut max_x, &mut max_y);
+
+        let mut u: f32 = 0f32;
+        let mut v: f32 = 0f32;
+
+        if width < 1u32 || height < 1u32 { return; }
+        max_x = max_x.min(width - 1u32);
+        max_y = max_y.min(height - 1u32);
+
+        for x in min_x ..= max_x {
+            for y in min_y ..= max_y {
+                let mut mask: u32 = 0u32;
+                for (i, (ox, oy)) in offsets.iter().enumerate() {
+                    if self.contains_point((x as f32) + ox, (y as f32) + oy) { mask |= 1u32 << i; }
+                }
+                if mask == 0u32 { continue; }
+
+                // Samples can cover a pixel whose center lies just outside the quad, so fall back to the closed form
+                if !self.get_uv(&Vector3D { x: x as f32, y: y as f32, z: 0f32 }, &mut u, &mut v) {
+                    match self.get_uv_inverse(x as f32, y as f32) {
+                        Some((iu, iv)) => {
+                            u = iu.clamp(0f32, 1f32);
+                            v = iv.clamp(0f32, 1f32);
+                        },
+                        None => continue
+                    }
+                }
+                f(x, y, u, v, mask);
+            }
+        }
+    }
+
+    // Solves get_point(u, v) = (x, y) in screen space by inverting the bilinear interpolation directly.
+    // The result may lie outside of [0, 1] when the point is outside of the quad.
+    pub fn get_uv_inverse(&self, x: f32, y: f32) -> Option<(f32, f32)> {
+        let cross = | ax: f32, ay: f32, bx: f32, by: f32 | ax * by - ay * bx;
+        let (ex, ey) = (self.tr.x - self.tl.x, self.tr.y - self.tl.y);
+        let (fx, fy) = (self.bl.x - self.tl.x, self.bl.y - self.tl.y);
+        let (gx, gy) = (self.tl.x - self.tr.x + self.br.x - self.bl.x, self.tl.y - self.tr.y + self.br.y - self.bl.y);
+        let (hx, hy) = (x - self.tl.x, y - self.tl.y);
+
+        let k2: f32 = cross(gx, gy, fx, fy);
+        let k1: f32 = cross(ex, ey, fx, fy) + cross(hx, hy, gx, gy);
+        let k0: f32 = cross(hx, hy, ex, ey);
+
+        let u_for = | v: f32 | {
+            let (dx, dy) = (ex + gx * v, ey + gy * v);
+            if dx.abs() >= dy.abs() { (hx - fx * v) / dx } else { (hy - fy * v) / dy }
+        };
+        let in_range = | t: f32 | (-0.001f32 ..= 1.001f32).contains(&t);
+
+        if k2.abs() <= f32::EPSILON {
+            if k1.abs() <= f32::EPSILON { return None; }
+            let v: f32 = -k0 / k1;
+            return Some((u_for(v), v));
+        }
+        let disc: f32 = k1 * k1 - 4f32 * k0 * k2;
+        if disc < 0f32 { return None; }
+        let w: f32 = disc.sqrt();
+        let v: f32 = (-k1 - w) / (2f32 * k2);
+        let u: f32 = u_for(v);
+        if in_range(u) && in_range(v) { return Some((u, v)); }
+        let v: f32 = (-k1 + w) / (2f32 * k2);
+        return Some((u_for(v), v));
+    }
+
+    // Exact screen-space test against the two triangles of the quad, for either winding
+    pub fn contains_point(&self, x: f32, y: f32) -> bool {
+        let inside = | a: &Vector3D, b: &Vector3D, c: &Vector3D | {
+            let e0: f32 = (b.x - a.x) * (y - a.y) - (b.y - a.y) * (x - a.x);
+            let e1: f32 = (c.x - b.x) * (y - b.y) - (c.y - b.y) * (x - b.x);
+            let e2: f32 = (a.x - c.x) * (y - c.y) - (a.y - c.y) * (x - c.x);
+            (e0 >= 0f32 && e1 >= 0f32 && e2 >= 0f32) || (e0 <= 0f32 && e1 <= 0f32 && e2 <= 0f32)
+        };
+        return inside(&self.tl, &self.tr, &self.bl) || inside(&self.tr, &self.br, &self.bl);
+    }
+
     fn get_u_binary_search_root(&self, point: &Vector3D, line: &mut Line, u: &mut f32, threshold_sqr: &f32) -> bool {
         return self.get_u_binary_search(point, line, u, 0.5f32, 0.5f32, threshold_sqr);
     }
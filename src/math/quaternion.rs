@@ -1,5 +1,6 @@
 use crate::math::vector::Vector3D;
 use crate::math::VectorLike;
+use std::f32::consts::PI;
 
 const QUATERNION_FLAG_UNIT: u8 =                0b__1;
 const QUATERNION_FLAG_POSITIVE_POLAR: u8 =      0b_10; // positive polar
@@ -68,10 +69,35 @@ impl Quaternion {
         };
     }
 
+    pub fn look_rotation(forward: &Vector3D) -> Self {
+        let mut dir: Vector3D = forward.copy();
+        dir.normalize();
+
+        let dot: f32 = dir.z;
+        if dot >= 1f32 - f32::EPSILON {
+            return Quaternion::identity();
+        }
+        if dot <= f32::EPSILON - 1f32 {
+            return Quaternion::from_principal(PI);
+        }
+
+        let mut axis: Vector3D = Vector3D::new(0f32, 0f32, 1f32).cross(&dir);
+        axis.normalize();
+        let half: f32 = dot.acos() * 0.5f32;
+        let s: f32 = half.sin();
+        return Self {
+            w: half.cos(),
+            x: axis.x * s,
+            y: axis.y * s,
+            z: axis.z * s,
+            flags: QUATERNION_FLAG_UNIT
+        };
+    }
+
     pub fn copy(&self) -> Self {
         return Self {
             w: self.w,
-            x: self.w,
+            x: self.x,
             y: self.y,
             z: self.z,
             flags: self.flags
@@ -19,8 +19,7 @@ impl Transform {
         }
     }
 
-    #[allow(dead_code)]
-    fn copy(&self) -> Self {
+    pub fn copy(&self) -> Self {
         return Self {
             translation: self.translation.copy(),
             rotation: self.rotation.copy(),
@@ -44,37 +43,51 @@ impl Transform {
         self.scale.multiply_scalar(amt);
     }
 
+    // Puts other, given relative to this transform, into the space this transform is relative to, as with a child
+    // and its parent. Scale that isn't uniform can't be carried through a rotation exactly, so it is only multiplied.
     pub fn transform(&self, other: &mut Transform) {
-        other.translate(&self.translation);
-        other.rotate(&self.rotation);
-        other.scale(&self.scale);
+        self.transform_vector(&mut other.translation);
+
+        let mut rotation: Quaternion = self.rotation.copy();
+        rotation.multiply(&other.rotation);
+        other.rotation = rotation;
+
+        other.scale.multiply_vector(&self.scale);
     }
 
+    // Undoes transform, so that other is relative to this transform again
     pub fn inverse_transform(&self, other: &mut Transform) {
-        let mut inv_translation: Vector3D = self.translation.copy();
-        inv_translation.negate();
-        other.translate(&inv_translation);
+        self.inverse_transform_vector(&mut other.translation);
 
         let mut inv_rotation: Quaternion = self.rotation.copy();
         inv_rotation.invert();
-        other.rotate(&inv_rotation);
+        inv_rotation.multiply(&other.rotation);
+        other.rotation = inv_rotation;
 
         other.scale.divide_vector(&self.scale);
     }
 
+    // Scales, then rotates, then translates
     pub fn transform_vector(&self, vec: &mut Vector3D) {
-        *vec = self.rotation.rotate(vec);
         vec.multiply_vector(&self.scale);
+        *vec = self.rotation.rotate(vec);
         vec.add_vector(&self.translation);
     }
 
     pub fn inverse_transform_vector(&self, vec: &mut Vector3D) {
         vec.subtract_vector(&self.translation);
-        vec.divide_vector(&self.scale);
 
         let mut inv_rotation: Quaternion = self.rotation.copy();
         inv_rotation.invert();
         *vec = inv_rotation.rotate(vec);
+
+        vec.divide_vector(&self.scale);
+    }
+
+    // Normals scale inversely to the surface, and aren't moved. The result is left unnormalized.
+    pub fn transform_normal(&self, vec: &mut Vector3D) {
+        vec.divide_vector(&self.scale);
+        *vec = self.rotation.rotate(vec);
     }
 
     pub fn transform_quad(&self, quad: &mut Quad) {
@@ -92,3 +105,75 @@ impl Transform {
     }
 
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::testing::assert_close;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn sample() -> Transform {
+        let mut ret: Transform = Transform::new();
+        ret.translate(&Vector3D::new(1f32, -2f32, 5f32));
+        ret.rotate(&Quaternion::from_euler(0.3f32, 1.1f32, -0.4f32));
+        ret.scale(&Vector3D::new(2f32, 0.5f32, 3f32));
+        return ret;
+    }
+
+    #[test]
+    fn scales_before_rotating() {
+        // A quarter turn about y takes x to -z, so scaling x afterwards would leave the length alone
+        let mut t: Transform = Transform::new();
+        t.rotate(&Quaternion::from_principal(FRAC_PI_2));
+        t.scale(&Vector3D::new(2f32, 1f32, 1f32));
+        let mut v: Vector3D = Vector3D::new(1f32, 0f32, 0f32);
+        t.transform_vector(&mut v);
+        assert_close(&v, &Vector3D::new(0f32, 0f32, -2f32));
+    }
+
+    #[test]
+    fn inverse_transform_vector_undoes_transform_vector() {
+        let t: Transform = sample();
+        for original in [ Vector3D::new(0f32, 0f32, 0f32), Vector3D::new(1f32, 2f32, 3f32), Vector3D::new(-4f32, 0.5f32, 7f32) ] {
+            let mut v: Vector3D = original.copy();
+            t.transform_vector(&mut v);
+            t.inverse_transform_vector(&mut v);
+            assert_close(&v, &original);
+        }
+    }
+
+    #[test]
+    fn composes_child_into_parent_space() {
+        let mut parent: Transform = Transform::new();
+        parent.translate(&Vector3D::new(0f32, 0f32, 10f32));
+        parent.rotate(&Quaternion::from_principal(FRAC_PI_2));
+        parent.scale_scalar(2f32);
+        let mut child: Transform = Transform::new();
+        child.translate(&Vector3D::new(1f32, 0f32, 0f32));
+        child.rotate(&Quaternion::from_euler(0.2f32, 0f32, 0.7f32));
+        child.scale_scalar(0.5f32);
+
+        let mut world: Transform = child.copy();
+        parent.transform(&mut world);
+        // The child sits two units along the parent's rotated x axis
+        assert_close(&world.translation, &Vector3D::new(0f32, 0f32, 8f32));
+        for point in [ Vector3D::new(1f32, 0f32, 0f32), Vector3D::new(0.3f32, -1f32, 2f32) ] {
+            let mut nested: Vector3D = point.copy();
+            child.transform_vector(&mut nested);
+            parent.transform_vector(&mut nested);
+            let mut composed: Vector3D = point.copy();
+            world.transform_vector(&mut composed);
+            assert_close(&composed, &nested);
+        }
+
+        parent.inverse_transform(&mut world);
+        assert_close(&world.translation, &child.translation);
+        assert_close(&world.scale, &child.scale);
+        let mut a: Vector3D = Vector3D::new(0f32, 1f32, 0f32);
+        let mut b: Vector3D = a.copy();
+        world.transform_vector(&mut a);
+        child.transform_vector(&mut b);
+        assert_close(&a, &b);
+    }
+
+}
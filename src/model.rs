@@ -1,18 +1,54 @@
 pub mod platonic;
 pub mod sphere;
+pub mod torus;
+pub mod cylinder;
+pub mod plane;
+pub mod parametric;
+pub mod terrain;
+pub mod vox;
+pub mod stl;
+pub mod ply;
+pub mod obj;
+pub mod gltf;
+pub mod json;
+pub mod material;
 
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+use crate::display::canvas::{ColorSpace, RGBA8, RGBA8F};
 use crate::math::quad::Quad;
+use crate::model::material::Material;
 use crate::math::transform::Transform;
+use crate::math::vector::Vector3D;
+use crate::math::VectorLike;
 
 struct ModelFace {
-    quad: Quad,
-    u: [f32; 4],
-    v: [f32; 4]
+    vertices: [usize; 4], // tl, tr, bl, br, into the model's vertices
+    uvs: [usize; 4], // tl, tr, bl, br, into the model's UVs
+    normals: Option<[usize; 4]>, // tl, tr, bl, br, into the model's normals, for smooth shading
+    colors: Option<[RGBA8; 4]> // tl, tr, bl, br, multiplied with the texture
 }
 
+// One corner of a face, as written to model files
+pub struct FaceCorner {
+    pub position: Vector3D,
+    pub uv: (f32, f32),
+    pub normal: Option<Vector3D>,
+    pub color: Option<RGBA8>
+}
+
+// Faces index into shared lists of vertices, UVs and normals, so that a vertex used by several faces is only stored,
+// transformed and projected once
 pub struct Model {
     pub transform: Transform,
+    pub material: Material,
+    vertices: Vec<Vector3D>,
+    uvs: Vec<(f32, f32)>,
+    normals: Vec<Vector3D>,
     faces: Vec<ModelFace>,
+    vertex_lookup: HashMap<[u32; 3], usize>, // vertices added by the face builders, by position
+    uv_lookup: HashMap<[u32; 2], usize> // UVs added by the face builders, by value
 }
 
 impl Model {
@@ -20,33 +56,133 @@ impl Model {
     pub fn new() -> Self {
         Self {
             transform: Transform::new(),
-            faces: Vec::new()
+            material: Material::new(),
+            vertices: Vec::new(),
+            uvs: Vec::new(),
+            normals: Vec::new(),
+            faces: Vec::new(),
+            vertex_lookup: HashMap::new(),
+            uv_lookup: HashMap::new()
         }
     }
 
     pub fn expect(size: usize) -> Self {
         Self {
             transform: Transform::new(),
-            faces: Vec::with_capacity(size)
+            material: Material::new(),
+            vertices: Vec::with_capacity(size),
+            uvs: Vec::with_capacity(size),
+            normals: Vec::new(),
+            faces: Vec::with_capacity(size),
+            vertex_lookup: HashMap::with_capacity(size),
+            uv_lookup: HashMap::new()
+        }
+    }
+
+    pub fn add_vertex(&mut self, vertex: Vector3D) -> usize {
+        self.vertices.push(vertex);
+        return self.vertices.len() - 1usize;
+    }
+
+    pub fn add_uv(&mut self, u: f32, v: f32) -> usize {
+        self.uvs.push((u, v));
+        return self.uvs.len() - 1usize;
+    }
+
+    pub fn add_normal(&mut self, normal: Vector3D) -> usize {
+        self.normals.push(normal);
+        return self.normals.len() - 1usize;
+    }
+
+    // Adds a face from the indices of its vertices and UVs, in the order tl, tr, bl, br
+    pub fn add_indexed_face(&mut self, vertices: [usize; 4], uvs: [usize; 4]) {
+        for i in vertices {
+            if i >= self.vertices.len() { panic!("Vertex {} out of bounds for length {}", i, self.vertices.len()); }
         }
+        for i in uvs {
+            if i >= self.uvs.len() { panic!("UV {} out of bounds for length {}", i, self.uvs.len()); }
+        }
+        self.faces.push(ModelFace { vertices, uvs, normals: None, colors: None });
     }
 
     pub fn add_face(&mut self, quad: Quad) {
-        self.faces.push(ModelFace {
-            quad, u: [ 0.0, 1.0, 0.0, 1.0 ], v: [ 0.0, 0.0, 1.0, 1.0 ]
-        });
+        self.add_face_uv(quad, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 1.0);
     }
 
+    // Adds a face from its corners and their UVs. Corners and UVs equal to ones added by this or add_triangle before
+    // are shared with those faces.
     pub fn add_face_uv(&mut self, quad: Quad, u_tl: f32, v_tl: f32, u_tr: f32, v_tr: f32, u_bl: f32, v_bl: f32, u_br: f32, v_br: f32) {
-        self.faces.push(ModelFace {
-            quad, u: [ u_tl, u_tr, u_bl, u_br ], v: [ v_tl, v_tr, v_bl, v_br ]
-        });
+        let vertices: [usize; 4] = [ self.shared_vertex(quad.tl), self.shared_vertex(quad.tr), self.shared_vertex(quad.bl), self.shared_vertex(quad.br) ];
+        let uvs: [usize; 4] = [ self.shared_uv(u_tl, v_tl), self.shared_uv(u_tr, v_tr), self.shared_uv(u_bl, v_bl), self.shared_uv(u_br, v_br) ];
+        self.add_indexed_face(vertices, uvs);
+    }
+
+    // Stored as a quad with its last two corners together. The front is the side the corners run counter-clockwise
+    // around, in a right-handed space. Corners are shared as with add_face_uv.
+    pub fn add_triangle(&mut self, a: Vector3D, b: Vector3D, c: Vector3D) {
+        let vertices: [usize; 3] = [ self.shared_vertex(a), self.shared_vertex(b), self.shared_vertex(c) ];
+        let uvs: [usize; 3] = [ self.shared_uv(0f32, 0f32), self.shared_uv(1f32, 0f32), self.shared_uv(1f32, 1f32) ];
+        self.add_indexed_face([ vertices[0], vertices[1], vertices[2], vertices[2] ], [ uvs[0], uvs[1], uvs[2], uvs[2] ]);
+    }
+
+    // Adding zero makes -0 and 0 the same key
+    fn shared_vertex(&mut self, vertex: Vector3D) -> usize {
+        let key: [u32; 3] = [ vertex.x, vertex.y, vertex.z ].map(| c | (c + 0f32).to_bits());
+        if let Some(idx) = self.vertex_lookup.get(&key) {
+            return *idx;
+        }
+        let idx: usize = self.add_vertex(vertex);
+        self.vertex_lookup.insert(key, idx);
+        return idx;
+    }
+
+    fn shared_uv(&mut self, u: f32, v: f32) -> usize {
+        let key: [u32; 2] = [ u, v ].map(| c | (c + 0f32).to_bits());
+        if let Some(idx) = self.uv_lookup.get(&key) {
+            return *idx;
+        }
+        let idx: usize = self.add_uv(u, v);
+        self.uv_lookup.insert(key, idx);
+        return idx;
+    }
+
+    // Moves the vertices so that their bounds are centered on the origin, and scales them so that the largest side
+    // of the bounds is size long. Models loaded from files can come in any units.
+    pub fn center_and_fit(&mut self, size: f32) {
+        center_and_fit_points(&mut self.vertices.iter_mut().collect::<Vec<&mut Vector3D>>(), size);
+        // The vertices have moved away from their keys
+        self.vertex_lookup.clear();
+    }
+
+    // Gives a face its own normal at each corner, in the order tl, tr, bl, br, so that it is shaded smoothly
+    pub fn set_face_normals(&mut self, idx: usize, normals: [Vector3D; 4]) {
+        let indices: [usize; 4] = normals.map(| n | self.add_normal(n));
+        self.set_face_normal_indices(idx, indices);
+    }
+
+    // As set_face_normals, with the indices of normals already added to the model
+    pub fn set_face_normal_indices(&mut self, idx: usize, normals: [usize; 4]) {
+        self.index_check(idx);
+        for i in normals {
+            if i >= self.normals.len() { panic!("Normal {} out of bounds for length {}", i, self.normals.len()); }
+        }
+        self.faces[idx].normals = Some(normals);
+    }
+
+    // Gives a face its own color at each corner, in the order tl, tr, bl, br, blended across it
+    pub fn set_face_colors(&mut self, idx: usize, colors: [RGBA8; 4]) {
+        self.index_check(idx);
+        self.faces[idx].colors = Some(colors);
     }
 
     pub fn get_face_count(&self) -> usize {
         return self.faces.len();
     }
 
+    pub fn get_vertex_count(&self) -> usize {
+        return self.vertices.len();
+    }
+
     fn index_check(&self, idx: usize) {
         if idx >= self.faces.len() {
             panic!("Index {} out of bounds for length {}", idx, self.faces.len());
@@ -54,18 +190,136 @@ impl Model {
     }
 
     pub fn get_face(&self, idx: usize) -> Quad {
-        self.index_check(idx);
-        let mut ret: Quad = self.faces[idx].quad.copy();
+        let mut ret: Quad = self.get_face_from(idx, &self.vertices);
         self.transform.transform_quad(&mut ret);
         return ret;
     }
 
-    pub fn remap_face_uv(&self, idx: usize, u: &mut f32, v: &mut f32) {
+    // Every vertex with the model's transform applied, in the order faces index them
+    pub fn get_world_vertices(&self) -> Vec<Vector3D> {
+        return self.vertices.iter().map(| v | {
+            let mut ret: Vector3D = v.copy();
+            self.transform.transform_vector(&mut ret);
+            ret
+        }).collect();
+    }
+
+    // Indices of a face's corners into the model's vertices, in the order tl, tr, bl, br
+    pub fn get_face_vertices(&self, idx: usize) -> [usize; 4] {
+        self.index_check(idx);
+        return self.faces[idx].vertices;
+    }
+
+    // Corners of a face taken from a list of vertices in the model's order, such as after they have been transformed
+    // or projected together
+    pub fn get_face_from(&self, idx: usize, vertices: &[Vector3D]) -> Quad {
+        self.index_check(idx);
+        let corners: &[usize; 4] = &self.faces[idx].vertices;
+        return Quad {
+            tl: vertices[corners[0]].copy(),
+            tr: vertices[corners[1]].copy(),
+            bl: vertices[corners[2]].copy(),
+            br: vertices[corners[3]].copy()
+        };
+    }
+
+    // Corners of a face in order around it, counter-clockwise seen from the front in a right-handed space, with any
+    // corner that repeats the one before it dropped, so that triangles come out with three. The model's transform is
+    // applied if bake is set.
+    pub fn get_face_polygon(&self, idx: usize, bake: bool) -> Vec<FaceCorner> {
         self.index_check(idx);
 
         let face: &ModelFace = &self.faces[idx];
-        let us: &[f32; 4] = &face.u;
-        let vs: &[f32; 4] = &face.v;
+        let mut ret: Vec<FaceCorner> = Vec::with_capacity(4usize);
+        // tl, tr, br, bl
+        for i in [ 0usize, 1usize, 3usize, 2usize ] {
+            let mut position: Vector3D = self.vertices[face.vertices[i]].copy();
+            let mut normal: Option<Vector3D> = face.normals.as_ref().map(| n | self.normals[n[i]].copy());
+            if bake {
+                self.transform.transform_vector(&mut position);
+                if let Some(n) = normal.as_mut() {
+                    self.transform.transform_normal(n);
+                    n.normalize();
+                }
+            }
+            if ret.last().is_some_and(| c: &FaceCorner | c.position.fuzzy_equals(&position)) { continue; }
+            ret.push(FaceCorner { position, uv: self.uvs[face.uvs[i]], normal, color: face.colors.map(| c | c[i]) });
+        }
+        if ret.len() > 1usize && ret[0].position.fuzzy_equals(&ret[ret.len() - 1usize].position) {
+            ret.pop();
+        }
+        return ret;
+    }
+
+    // Distance from the model origin to its furthest vertex, after scaling
+    pub fn get_radius(&self) -> f32 {
+        let mut ret: f32 = 0f32;
+        for vertex in self.vertices.iter() {
+            let mut scaled: Vector3D = vertex.copy();
+            scaled.multiply_vector(&self.transform.scale);
+            ret = ret.max(scaled.norm());
+        }
+        ret
+    }
+
+    // World space normal interpolated from the face's corner normals, if it has any
+    pub fn get_face_normal(&self, idx: usize, u: f32, v: f32) -> Option<Vector3D> {
+        self.index_check(idx);
+
+        let normals: &[usize; 4] = self.faces[idx].normals.as_ref()?;
+        let top: Vector3D = Vector3D::lerp(&self.normals[normals[0]], &self.normals[normals[1]], u);
+        let bottom: Vector3D = Vector3D::lerp(&self.normals[normals[2]], &self.normals[normals[3]], u);
+        let mut ret: Vector3D = Vector3D::lerp(&top, &bottom, v);
+        self.transform.transform_normal(&mut ret);
+        ret.normalize();
+        return Some(ret);
+    }
+
+    // Color interpolated from the face's corner colors in the given color space, if it has any
+    pub fn get_face_color(&self, idx: usize, u: f32, v: f32, space: ColorSpace) -> Option<RGBA8F> {
+        self.index_check(idx);
+
+        let colors: &[RGBA8; 4] = self.faces[idx].colors.as_ref()?;
+        let c: [RGBA8F; 4] = colors.map(| col | space.decode(&col));
+        let lerp = | a: f32, b: f32, c: f32, d: f32 | {
+            let top: f32 = a + (b - a) * u;
+            let bottom: f32 = c + (d - c) * u;
+            top + (bottom - top) * v
+        };
+        return Some(RGBA8F {
+            r: lerp(c[0].r, c[1].r, c[2].r, c[3].r),
+            g: lerp(c[0].g, c[1].g, c[2].g, c[3].g),
+            b: lerp(c[0].b, c[1].b, c[2].b, c[3].b),
+            a: lerp(c[0].a, c[1].a, c[2].a, c[3].a)
+        });
+    }
+
+    // U and V of a face's corners, in the order tl, tr, bl, br
+    fn get_face_uvs(&self, idx: usize) -> ([f32; 4], [f32; 4]) {
+        let uvs: [(f32, f32); 4] = self.faces[idx].uvs.map(| i | self.uvs[i]);
+        return (uvs.map(| uv | uv.0), uvs.map(| uv | uv.1));
+    }
+
+    // Partial derivatives of the remapped UV with respect to the face UV, as [du/du, dv/du, du/dv, dv/dv]
+    pub fn get_face_uv_partials(&self, idx: usize, u: f32, v: f32) -> [f32; 4] {
+        self.index_check(idx);
+
+        let (us, vs) = self.get_face_uvs(idx);
+        let uc: f32 = us[3] - us[2] - us[1] + us[0];
+        let vc: f32 = vs[3] - vs[2] - vs[1] + vs[0];
+
+        return [
+            (us[1] - us[0]) + uc * v,
+            (vs[1] - vs[0]) + vc * v,
+            (us[2] - us[0]) + uc * u,
+            (vs[2] - vs[0]) + vc * u
+        ];
+    }
+
+    pub fn remap_face_uv(&self, idx: usize, u: &mut f32, v: &mut f32) {
+        self.index_check(idx);
+
+        let (us, vs) = self.get_face_uvs(idx);
 
         let u_t: f32 = (us[1] - us[0]) * (*u) + us[0];
         let u_b: f32 = (us[3] - us[2]) * (*u) + us[2];
@@ -78,3 +332,153 @@ impl Model {
     }
 
 }
+
+// Problem reading a model from a file
+pub struct ModelFileError {
+    msg: Box<str>
+}
+
+impl ModelFileError {
+    pub fn new(msg: &str) -> ModelFileError {
+        ModelFileError { msg: msg.into() }
+    }
+}
+
+impl Debug for ModelFileError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.msg)
+    }
+}
+impl Display for ModelFileError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.msg)
+    }
+}
+impl Error for ModelFileError { }
+
+// Moves the points so that their bounds are centered on the origin, and scales them so that the largest side of the
+// bounds is size long, as Model::center_and_fit does for a model's vertices
+pub fn center_and_fit_points(points: &mut [&mut Vector3D], size: f32) {
+    let mut min: Vector3D = Vector3D::uniform(f32::INFINITY);
+    let mut max: Vector3D = Vector3D::uniform(f32::NEG_INFINITY);
+    for p in points.iter() {
+        min = Vector3D::new(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z));
+        max = Vector3D::new(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z));
+    }
+    let extent: f32 = (max.x - min.x).max(max.y - min.y).max(max.z - min.z);
+    if !extent.is_finite() || extent <= 0f32 { return; }
+
+    let mut center: Vector3D = Vector3D::lerp(&min, &max, 0.5f32);
+    center.negate();
+    let scale: f32 = size / extent;
+    for p in points.iter_mut() {
+        p.add_vector(&center);
+        p.multiply_scalar(scale);
+    }
+}
+
+// Fixtures shared by the tests of the model loaders
+#[cfg(test)]
+pub(crate) mod testing {
+    use std::fs;
+    use std::path::PathBuf;
+    use tempfile::{tempdir, TempDir};
+    use crate::math::quad::Quad;
+    use crate::math::vector::Vector3D;
+    use crate::math::VectorLike;
+    use crate::model::{Model, ModelFileError};
+
+    // Writes the bytes to a file with the given extension, and loads it
+    pub(crate) fn load_bytes<T>(ext: &str, bytes: &[u8], loader: impl FnOnce(PathBuf) -> Result<T, ModelFileError>) -> Result<T, ModelFileError> {
+        let dir: TempDir = tempdir().unwrap();
+        let path: PathBuf = dir.path().join(format!("test.{}", ext));
+        fs::write(&path, bytes).unwrap();
+        loader(path)
+    }
+
+    // The renderer culls faces whose normal points away from the camera, so every face of a closed solid has to
+    // wind with its normal pointing out of it. inside gives a point just behind each face.
+    pub(crate) fn assert_faces_point_outward(name: &str, model: &Model, inside: impl Fn(&Vector3D) -> Vector3D) {
+        assert!(model.get_face_count() > 0usize, "{} has no faces", name);
+        for idx in 0 .. model.get_face_count() {
+            let face: Quad = model.get_face(idx);
+            let center: Vector3D = face.get_center();
+            let mut out: Vector3D = center.copy();
+            out.subtract_vector(&inside(&center));
+            assert!(face.get_normal().dot(&out) > 0f32, "{} face {} points inward", name, idx);
+        }
+    }
+
+    // Convex solids can use the average of their face centers
+    pub(crate) fn assert_convex_faces_point_outward(name: &str, model: &Model) {
+        let mut centroid: Vector3D = Vector3D::zero();
+        for idx in 0 .. model.get_face_count() {
+            centroid.add_vector(&model.get_face(idx).get_center());
+        }
+        centroid.divide_scalar(model.get_face_count() as f32);
+        assert_faces_point_outward(name, model, | _ | centroid.copy());
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::testing::{assert_convex_faces_point_outward, assert_faces_point_outward};
+    use crate::model::platonic::{cube, dodecahedron, icosahedron, octahedron, tetrahedron, CubeLayout};
+    use crate::model::sphere::uv_sphere;
+    use crate::model::torus::torus;
+    use crate::model::cylinder::{capsule, cone, cylinder};
+
+    #[test]
+    fn platonic_solids_wind_outward() {
+        for layout in [ CubeLayout::Same, CubeLayout::Cross, CubeLayout::Strip3x2, CubeLayout::Strip6x1 ] {
+            assert_convex_faces_point_outward("cube", &cube(layout));
+        }
+        assert_convex_faces_point_outward("tetrahedron", &tetrahedron());
+        assert_convex_faces_point_outward("octahedron", &octahedron());
+        assert_convex_faces_point_outward("dodecahedron", &dodecahedron());
+        assert_convex_faces_point_outward("icosahedron", &icosahedron());
+    }
+
+    #[test]
+    fn curved_solids_wind_outward() {
+        assert_convex_faces_point_outward("uv_sphere", &uv_sphere(16u16, 8u16));
+        assert_convex_faces_point_outward("cylinder", &cylinder(1f32, 2f32, 16u16, 4u16));
+        assert_convex_faces_point_outward("cone", &cone(1f32, 2f32, 16u16, 4u16));
+        assert_convex_faces_point_outward("capsule", &capsule(0.6f32, 1.6f32, 16u16, 8u16));
+        // Inside the torus is the middle of its tube, on the ring around the y axis
+        assert_faces_point_outward("torus", &torus(1.2f32, 0.5f32, 16u16, 8u16), | p | {
+            let mut ring: Vector3D = Vector3D::new(p.x, 0f32, p.z);
+            ring.normalize();
+            ring.multiply_scalar(1.2f32);
+            ring
+        });
+    }
+
+    #[test]
+    fn solids_share_their_corners() {
+        for layout in [ CubeLayout::Same, CubeLayout::Cross, CubeLayout::Strip3x2, CubeLayout::Strip6x1 ] {
+            assert_eq!(cube(layout).get_vertex_count(), 8usize);
+        }
+        assert_eq!(tetrahedron().get_vertex_count(), 4usize);
+        assert_eq!(octahedron().get_vertex_count(), 6usize);
+        assert_eq!(dodecahedron().get_vertex_count(), 20usize);
+        assert_eq!(icosahedron().get_vertex_count(), 12usize);
+    }
+
+    #[test]
+    fn faces_added_after_fitting_are_not_shared_with_moved_vertices() {
+        let mut model: Model = Model::new();
+        model.add_triangle(Vector3D::new(0f32, 0f32, 0f32), Vector3D::new(-0f32, 4f32, 0f32), Vector3D::new(4f32, 0f32, 0f32));
+        assert_eq!(model.get_vertex_count(), 3usize);
+        model.center_and_fit(2f32);
+        model.add_triangle(Vector3D::new(0f32, 0f32, 0f32), Vector3D::new(0f32, 4f32, 0f32), Vector3D::new(4f32, 0f32, 0f32));
+        assert_eq!(model.get_vertex_count(), 6usize);
+        assert_eq!(model.get_face(1usize).tr.y, 4f32);
+        // The second triangle's corners share among themselves again
+        model.add_triangle(Vector3D::new(0f32, 0f32, 0f32), Vector3D::new(0f32, 4f32, 0f32), Vector3D::new(0f32, 0f32, 4f32));
+        assert_eq!(model.get_vertex_count(), 7usize);
+    }
+
+}
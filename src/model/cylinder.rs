@@ -0,0 +1,127 @@
+use crate::model::Model;
+use core::f32::consts::PI;
+use crate::math::quad::Quad;
+use crate::math::vector::Vector3D;
+use crate::model::parametric::ParametricSurface;
+
+// Solids of revolution around the y axis, centered on the origin. Their sides wrap the texture once around
+// along u with v running from top to bottom, and flat caps show the inscribed circle of the texture.
+
+pub fn cylinder(radius: f32, height: f32, mut segments: u16, rings: u16) -> Model {
+    if segments < 3u16 { segments = 3u16; }
+    let half: f32 = height / 2f32;
+    let point = | u: f32, v: f32 | {
+        let ang: f32 = u * PI * 2f32;
+        Vector3D::new(radius * ang.cos(), half - v * height, radius * ang.sin())
+    };
+    let normal = | u: f32, _v: f32 | {
+        let ang: f32 = u * PI * 2f32;
+        Vector3D::new(ang.cos(), 0f32, ang.sin())
+    };
+
+    let mut ret: Model = ParametricSurface::new(point, segments, rings).wrap(true, false).normals(normal).build();
+    add_cap(&mut ret, radius, half, segments, true);
+    add_cap(&mut ret, radius, -half, segments, false);
+    ret
+}
+
+// Apex at the top, with a flat base
+pub fn cone(radius: f32, height: f32, mut segments: u16, rings: u16) -> Model {
+    if segments < 3u16 { segments = 3u16; }
+    let half: f32 = height / 2f32;
+    let point = | u: f32, v: f32 | {
+        let ang: f32 = u * PI * 2f32;
+        Vector3D::new(radius * v * ang.cos(), half - v * height, radius * v * ang.sin())
+    };
+    // Tilted up from the side by the slope, so the apex gets a normal for every direction around it
+    let normal = | u: f32, _v: f32 | {
+        let ang: f32 = u * PI * 2f32;
+        Vector3D::new(height * ang.cos(), radius, height * ang.sin())
+    };
+
+    let mut ret: Model = ParametricSurface::new(point, segments, rings).wrap(true, false).normals(normal).build();
+    add_cap(&mut ret, radius, -half, segments, false);
+    ret
+}
+
+// Cylinder of the given height between two hemispheres, so the whole capsule is height + 2 * radius tall.
+// The texture is spread along v by arc length, so it isn't stretched over the ends.
+pub fn capsule(radius: f32, height: f32, segments: u16, mut rings: u16) -> Model {
+    if rings < 2u16 { rings = 2u16; }
+    let half: f32 = height / 2f32;
+    let length: f32 = PI * radius + height;
+
+    let mut profile: Vec<(f32, f32, f32)> = Vec::with_capacity((rings as usize) * 2usize + 2usize);
+    for ring in 0 ..= rings {
+        let angle: f32 = (ring as f32) / (rings as f32) * PI / 2f32;
+        profile.push((radius * angle.sin(), half + radius * angle.cos(), angle * radius / length));
+    }
+    for ring in 0 ..= rings {
+        let angle: f32 = PI / 2f32 + (ring as f32) / (rings as f32) * PI / 2f32;
+        profile.push((radius * angle.sin(), -half + radius * angle.cos(), (angle * radius + height) / length));
+    }
+
+    revolve(&profile, segments)
+}
+
+// Sweeps a profile of (radius, y, v) points, listed from top to bottom, once around the y axis.
+// Unlike a parametric surface, the profile can space its rings and texture unevenly.
+fn revolve(profile: &[(f32, f32, f32)], mut segments: u16) -> Model {
+    if segments < 3u16 { segments = 3u16; }
+
+    let mut ret: Model = Model::expect((segments as usize) * profile.len());
+    for seg in 0 .. segments {
+        let u1: f32 = (seg as f32) / (segments as f32);
+        let u2: f32 = ((seg + 1u16) as f32) / (segments as f32);
+        let (x1, z1) = ((u1 * PI * 2f32).cos(), (u1 * PI * 2f32).sin());
+        let (x2, z2) = ((u2 * PI * 2f32).cos(), (u2 * PI * 2f32).sin());
+
+        for pair in profile.windows(2usize) {
+            let (r1, y1, v1) = pair[0];
+            let (r2, y2, v2) = pair[1];
+            if r1 == 0f32 && r2 == 0f32 { continue; }
+            ret.add_face_uv(
+                Quad {
+                    tl: Vector3D::new(x1 * r1, y1, z1 * r1),
+                    tr: Vector3D::new(x2 * r1, y1, z2 * r1),
+                    bl: Vector3D::new(x1 * r2, y2, z1 * r2),
+                    br: Vector3D::new(x2 * r2, y2, z2 * r2)
+                },
+                u1, v1,
+                u2, v1,
+                u1, v2,
+                u2, v2
+            );
+        }
+    }
+
+    ret
+}
+
+// Flat disc at height y facing up or down, as a fan of triangles around its center
+fn add_cap(model: &mut Model, radius: f32, y: f32, mut segments: u16, up: bool) {
+    if segments < 3u16 { segments = 3u16; }
+
+    // Seen from outside, u runs along x either way, which flips between the two sides
+    let flip: f32 = if up { 1f32 } else { -1f32 };
+    let center: Vector3D = Vector3D::new(0f32, y, 0f32);
+    for seg in 0 .. segments {
+        let mut a1: f32 = (seg as f32) / (segments as f32) * PI * 2f32;
+        let mut a2: f32 = ((seg + 1u16) as f32) / (segments as f32) * PI * 2f32;
+        if !up { std::mem::swap(&mut a1, &mut a2); }
+        let (x1, z1) = (a1.cos(), a1.sin());
+        let (x2, z2) = (a2.cos(), a2.sin());
+        model.add_face_uv(
+            Quad {
+                tl: center.copy(),
+                tr: Vector3D::new(x2 * radius, y, z2 * radius),
+                bl: Vector3D::new(x1 * radius, y, z1 * radius),
+                br: Vector3D::new(x1 * radius, y, z1 * radius)
+            },
+            0.5f32, 0.5f32,
+            0.5f32 + flip * x2 * 0.5f32, 0.5f32 - z2 * 0.5f32,
+            0.5f32 + flip * x1 * 0.5f32, 0.5f32 - z1 * 0.5f32,
+            0.5f32 + flip * x1 * 0.5f32, 0.5f32 - z1 * 0.5f32
+        );
+    }
+}
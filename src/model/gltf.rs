@@ -0,0 +1,699 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use image::DynamicImage;
+use crate::display::canvas::{ColorSpace, ImageCanvas, TextureWrap, WrapMode, RGBA8, RGBA8F};
+use crate::math::quad::Quad;
+use crate::math::quaternion::Quaternion;
+use crate::math::transform::Transform;
+use crate::math::vector::Vector3D;
+use crate::math::VectorLike;
+use crate::model::json::Json;
+use crate::model::{center_and_fit_points, Model, ModelFileError};
+
+const GLB_MAGIC: &[u8] = b"glTF";
+const GLB_CHUNK_JSON: u32 = 0x4e4f534au32;
+const GLB_CHUNK_BIN: u32 = 0x004e4942u32;
+
+// Required extensions that only add data this loader can go without, or that it already handles
+const IGNORABLE_EXTENSIONS: [&str; 3] = [ "KHR_mesh_quantization", "KHR_texture_transform", "KHR_materials_" ];
+
+struct Corner {
+    position: Vector3D,
+    normal: Option<Vector3D>,
+    uv: (f32, f32),
+    color: RGBA8
+}
+
+struct Triangle {
+    corners: [Corner; 3],
+    texture: Option<usize>
+}
+
+struct GltfMaterial {
+    factor: [f32; 4],
+    texture: Option<(usize, usize)> // texture, then texture coordinate set
+}
+
+// Loads the default scene of a glTF 2.0 file, either as .gltf JSON with its buffers and images beside it or embedded,
+// or as a single .glb. Node transforms are baked into the vertices. Base color factors and vertex colors become face
+// colors. There is one model for each base color texture, wrapping as its sampler says, and one for faces without a
+// texture. The models share their origin, and are centered on it and scaled together to fit in the same 2 unit box as
+// the cube.
+pub fn load_gltf<P: AsRef<Path>>(path: P) -> Result<Vec<Model>, ModelFileError> {
+    let bytes: Vec<u8> = fs::read(path.as_ref()).map_err(| err | ModelFileError::new(&format!("{}", err)))?;
+    let dir: PathBuf = path.as_ref().parent().map_or(PathBuf::new(), | p | p.to_path_buf());
+
+    let mut bin: Option<&[u8]> = None;
+    let doc: Json = if bytes.starts_with(GLB_MAGIC) {
+        let (json, chunk) = read_glb(&bytes)?;
+        bin = chunk;
+        Json::parse(json)?
+    } else {
+        Json::parse(std::str::from_utf8(&bytes).map_err(| _ | ModelFileError::new("Not a glTF file"))?)?
+    };
+
+    if let Some(version) = doc.get("asset").and_then(| a | a.get("version")).and_then(| v | v.as_str()) {
+        if !version.starts_with("2.") {
+            return Err(ModelFileError::new(&format!("Unsupported glTF version {}", version)));
+        }
+    }
+    for ext in doc.get("extensionsRequired").map_or(&[][..], | e | e.as_array()) {
+        let name: &str = ext.as_str().unwrap_or("");
+        if !IGNORABLE_EXTENSIONS.iter().any(| known | name.starts_with(known)) {
+            return Err(ModelFileError::new(&format!("Needs unsupported extension {}", name)));
+        }
+    }
+
+    let mut buffers: Vec<Vec<u8>> = Vec::new();
+    for (i, buffer) in list(&doc, "buffers").iter().enumerate() {
+        buffers.push(match buffer.get("uri").and_then(| u | u.as_str()) {
+            Some(uri) => read_uri(&dir, uri)?,
+            None if i == 0usize && bin.is_some() => bin.take().unwrap_or(&[]).to_vec(),
+            None => return Err(ModelFileError::new("Buffer without data"))
+        });
+    }
+
+    let materials: Vec<GltfMaterial> = list(&doc, "materials").iter().map(| material | {
+        let pbr: Option<&Json> = material.get("pbrMetallicRoughness");
+        let texture: Option<&Json> = pbr.and_then(| p | p.get("baseColorTexture"));
+        GltfMaterial {
+            factor: pbr.and_then(| p | p.get("baseColorFactor")).and_then(| f | f.as_floats::<4>()).unwrap_or([ 1f32; 4 ]),
+            // Textures without an image are left out, as if there were no texture
+            texture: texture.and_then(| t | t.get("index")).and_then(| i | i.as_usize())
+                .filter(| i | list(&doc, "textures").get(*i).and_then(| t | t.get("source")).is_some())
+                .map(| i | (i, texture.and_then(| t | t.get("texCoord")).and_then(| c | c.as_usize()).unwrap_or(0usize)))
+        }
+    }).collect();
+
+    let nodes: &[Json] = list(&doc, "nodes");
+    let locals: Vec<Transform> = nodes.iter().map(node_transform).collect();
+    let mut parents: Vec<Option<usize>> = vec![None; nodes.len()];
+    for (i, node) in nodes.iter().enumerate() {
+        for child in node.get("children").map_or(&[][..], | c | c.as_array()) {
+            if let Some(parent) = child.as_usize().and_then(| c | parents.get_mut(c)) {
+                *parent = Some(i);
+            }
+        }
+    }
+
+    // Nodes in the default scene, or every node if there are no scenes
+    let scene: Option<&Json> = list(&doc, "scenes").get(doc.get("scene").and_then(| s | s.as_usize()).unwrap_or(0usize));
+    let mut in_scene: Vec<bool> = vec![scene.is_none(); nodes.len()];
+    let mut stack: Vec<usize> = scene.map_or(Vec::new(), | s | s.get("nodes").map_or(&[][..], | n | n.as_array()).iter().filter_map(| n | n.as_usize()).collect());
+    while let Some(i) = stack.pop() {
+        if i >= nodes.len() || in_scene[i] { continue; }
+        in_scene[i] = true;
+        stack.extend(nodes[i].get("children").map_or(&[][..], | c | c.as_array()).iter().filter_map(| c | c.as_usize()));
+    }
+
+    let mut triangles: Vec<Triangle> = Vec::new();
+    for (i, node) in nodes.iter().enumerate() {
+        if !in_scene[i] { continue; }
+        let mesh: Option<&Json> = node.get("mesh").and_then(| m | m.as_usize()).and_then(| m | list(&doc, "meshes").get(m));
+        let Some(mesh) = mesh else { continue; };
+
+        // From the node up to the root, as each transform is applied in turn
+        let mut chain: Vec<&Transform> = Vec::new();
+        let mut at: Option<usize> = Some(i);
+        while let Some(n) = at {
+            if chain.len() > nodes.len() {
+                return Err(ModelFileError::new("Node hierarchy has a cycle"));
+            }
+            chain.push(&locals[n]);
+            at = parents[n];
+        }
+
+        for primitive in mesh.get("primitives").map_or(&[][..], | p | p.as_array()) {
+            let material: Option<&GltfMaterial> = primitive.get("material").and_then(| m | m.as_usize()).and_then(| m | materials.get(m));
+            read_primitive(&doc, &buffers, primitive, material, &chain, &mut triangles)?;
+        }
+    }
+    if triangles.is_empty() {
+        return Err(ModelFileError::new("No triangles in scene"));
+    }
+
+    // Fit together, so that the parts keep their places relative to each other
+    let mut positions: Vec<&mut Vector3D> = triangles.iter_mut().flat_map(| t | t.corners.iter_mut()).map(| c | &mut c.position).collect();
+    center_and_fit_points(&mut positions, 2f32);
+
+    // Models follow the order their textures were first used in
+    let mut textures: Vec<Option<usize>> = Vec::new();
+    for triangle in triangles.iter() {
+        if !textures.contains(&triangle.texture) {
+            textures.push(triangle.texture);
+        }
+    }
+
+    let mut ret: Vec<Model> = Vec::with_capacity(textures.len());
+    for texture in textures {
+        let mut model: Model = Model::expect(triangles.iter().filter(| t | t.texture == texture).count());
+        for triangle in triangles.iter().filter(| t | t.texture == texture) {
+            // Flipping z mirrors the model, which swapping the last two corners makes up for
+            let [ a, b, c ] = &triangle.corners;
+            model.add_face_uv(
+                Quad { tl: a.position.copy(), tr: c.position.copy(), bl: b.position.copy(), br: b.position.copy() },
+                a.uv.0, a.uv.1, c.uv.0, c.uv.1, b.uv.0, b.uv.1, b.uv.0, b.uv.1
+            );
+            let idx: usize = model.get_face_count() - 1usize;
+            model.set_face_colors(idx, [ a.color, c.color, b.color, b.color ]);
+            if let (Some(na), Some(nb), Some(nc)) = (&a.normal, &b.normal, &c.normal) {
+                model.set_face_normals(idx, [ na.copy(), nc.copy(), nb.copy(), nb.copy() ]);
+            }
+        }
+
+        match texture {
+            Some(idx) => {
+                let (image, wrap) = read_texture(&doc, &dir, &buffers, idx)?;
+                model.material.texture = Some(ImageCanvas::new(image));
+                model.material.wrap = Some(wrap);
+            },
+            None => model.material.set_color(&RGBA8::white())
+        }
+        ret.push(model);
+    }
+    Ok(ret)
+}
+
+// A top level array of the document, such as its nodes or meshes
+fn list<'a>(doc: &'a Json, key: &str) -> &'a [Json] {
+    doc.get(key).map_or(&[], | v | v.as_array())
+}
+
+// The JSON chunk and the binary chunk, if there is one
+fn read_glb(bytes: &[u8]) -> Result<(&str, Option<&[u8]>), ModelFileError> {
+    let read_u32 = | pos: usize | -> Result<u32, ModelFileError> {
+        bytes.get(pos .. pos + 4usize)
+            .map(| b | u32::from_le_bytes([ b[0], b[1], b[2], b[3] ]))
+            .ok_or_else(|| ModelFileError::new("Unexpected end of file"))
+    };
+    if read_u32(4usize)? != 2u32 {
+        return Err(ModelFileError::new("Unsupported GLB version"));
+    }
+
+    let mut json: Option<&str> = None;
+    let mut bin: Option<&[u8]> = None;
+    let mut pos: usize = 12usize;
+    while pos + 8usize <= bytes.len() {
+        let length: usize = read_u32(pos)? as usize;
+        let kind: u32 = read_u32(pos + 4usize)?;
+        let data: &[u8] = bytes.get(pos + 8usize .. pos + 8usize + length).ok_or_else(|| ModelFileError::new("Unexpected end of file"))?;
+        match kind {
+            GLB_CHUNK_JSON if json.is_none() => json = Some(std::str::from_utf8(data).map_err(| _ | ModelFileError::new("Invalid text in GLB"))?),
+            GLB_CHUNK_BIN if bin.is_none() => bin = Some(data),
+            _ => { }
+        }
+        pos += 8usize + length;
+    }
+    Ok((json.ok_or_else(|| ModelFileError::new("No JSON in GLB"))?, bin))
+}
+
+// Either a base64 data URI or a path relative to the file
+fn read_uri(dir: &Path, uri: &str) -> Result<Vec<u8>, ModelFileError> {
+    if let Some(rest) = uri.strip_prefix("data:") {
+        return match rest.split_once(',') {
+            Some((meta, data)) if meta.ends_with(";base64") => decode_base64(data).ok_or_else(|| ModelFileError::new("Invalid base64 data")),
+            _ => Err(ModelFileError::new("Unsupported data URI"))
+        };
+    }
+    let path: PathBuf = dir.join(decode_percent(uri));
+    fs::read(&path).map_err(| err | ModelFileError::new(&format!("{} ({})", err, path.display())))
+}
+
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let mut ret: Vec<u8> = Vec::with_capacity(text.len() * 3usize / 4usize);
+    let mut acc: u32 = 0u32;
+    let mut bits: u32 = 0u32;
+    for c in text.bytes() {
+        let value: u8 = match c {
+            b'A' ..= b'Z' => c - b'A',
+            b'a' ..= b'z' => c - b'a' + 26u8,
+            b'0' ..= b'9' => c - b'0' + 52u8,
+            b'+' | b'-' => 62u8,
+            b'/' | b'_' => 63u8,
+            b'=' => break,
+            b' ' | b'\t' | b'\n' | b'\r' => continue,
+            _ => return None
+        };
+        acc = (acc << 6) | (value as u32);
+        bits += 6u32;
+        if bits >= 8u32 {
+            bits -= 8u32;
+            ret.push((acc >> bits) as u8);
+            acc &= (1u32 << bits) - 1u32;
+        }
+    }
+    Some(ret)
+}
+
+fn decode_percent(uri: &str) -> String {
+    let bytes: &[u8] = uri.as_bytes();
+    let mut ret: Vec<u8> = Vec::with_capacity(bytes.len());
+    let mut i: usize = 0usize;
+    while i < bytes.len() {
+        let hex: Option<u8> = bytes.get(i + 1usize .. i + 3usize)
+            .and_then(| h | std::str::from_utf8(h).ok())
+            .and_then(| h | u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => { ret.push(b); i += 3usize; },
+            (b, _) => { ret.push(b); i += 1usize; }
+        }
+    }
+    String::from_utf8_lossy(&ret).into_owned()
+}
+
+fn read_image(doc: &Json, dir: &Path, buffers: &[Vec<u8>], idx: usize) -> Result<DynamicImage, ModelFileError> {
+    let image: &Json = list(doc, "images").get(idx).ok_or_else(|| ModelFileError::new("Missing image"))?;
+    let data: Vec<u8> = match (image.get("uri").and_then(| u | u.as_str()), image.get("bufferView").and_then(| v | v.as_usize())) {
+        (Some(uri), _) => read_uri(dir, uri)?,
+        (None, Some(view)) => {
+            let (buffer, offset, length, _) = buffer_view(doc, buffers, view)?;
+            buffer.get(offset .. offset + length).ok_or_else(|| ModelFileError::new("Image outside of its buffer"))?.to_vec()
+        },
+        _ => return Err(ModelFileError::new("Image without data"))
+    };
+    image::load_from_memory(&data).map_err(| err | ModelFileError::new(&format!("{}", err)))
+}
+
+// Image of a texture, and how its sampler wraps it, repeating by default
+fn read_texture(doc: &Json, dir: &Path, buffers: &[Vec<u8>], idx: usize) -> Result<(DynamicImage, TextureWrap), ModelFileError> {
+    let texture: &Json = list(doc, "textures").get(idx).ok_or_else(|| ModelFileError::new("Missing texture"))?;
+    let image: usize = texture.get("source").and_then(| s | s.as_usize()).ok_or_else(|| ModelFileError::new("Texture without an image"))?;
+    let sampler: Option<&Json> = texture.get("sampler").and_then(| s | s.as_usize()).and_then(| s | list(doc, "samplers").get(s));
+    let mode = | key: &str | match sampler.and_then(| s | s.get(key)).and_then(| w | w.as_usize()) {
+        Some(33071usize) => WrapMode::ClampToEdge,
+        Some(33648usize) => WrapMode::MirroredRepeat,
+        _ => WrapMode::Repeat
+    };
+    Ok((read_image(doc, dir, buffers, image)?, TextureWrap::new(mode("wrapS"), mode("wrapT"))))
+}
+
+// The buffer a view points into, with the view's offset, length and stride
+type BufferView<'a> = (&'a [u8], usize, usize, Option<usize>);
+
+fn buffer_view<'a>(doc: &Json, buffers: &'a [Vec<u8>], idx: usize) -> Result<BufferView<'a>, ModelFileError> {
+    let view: &Json = list(doc, "bufferViews").get(idx).ok_or_else(|| ModelFileError::new("Missing buffer view"))?;
+    let buffer: &Vec<u8> = view.get("buffer").and_then(| b | b.as_usize()).and_then(| b | buffers.get(b))
+        .ok_or_else(|| ModelFileError::new("Missing buffer"))?;
+    return Ok((
+        buffer,
+        view.get("byteOffset").and_then(| o | o.as_usize()).unwrap_or(0usize),
+        view.get("byteLength").and_then(| l | l.as_usize()).unwrap_or(0usize),
+        view.get("byteStride").and_then(| s | s.as_usize())
+    ));
+}
+
+// Every element of an accessor as a flat list, with the number of components in each. Integers are mapped to 0 - 1,
+// or -1 - 1 when signed, if the accessor says to or normalize is set.
+fn read_accessor(doc: &Json, buffers: &[Vec<u8>], idx: usize, normalize: bool) -> Result<(Vec<f64>, usize), ModelFileError> {
+    let accessor: &Json = list(doc, "accessors").get(idx).ok_or_else(|| ModelFileError::new("Missing accessor"))?;
+    let count: usize = accessor.get("count").and_then(| c | c.as_usize()).ok_or_else(|| ModelFileError::new("Accessor without a count"))?;
+    let components: usize = match accessor.get("type").and_then(| t | t.as_str()) {
+        Some("SCALAR") => 1usize,
+        Some("VEC2") => 2usize,
+        Some("VEC3") => 3usize,
+        Some("VEC4") | Some("MAT2") => 4usize,
+        Some("MAT3") => 9usize,
+        Some("MAT4") => 16usize,
+        _ => return Err(ModelFileError::new("Unknown accessor type"))
+    };
+    let component_type: usize = accessor.get("componentType").and_then(| c | c.as_usize()).unwrap_or(0usize);
+    let (size, max): (usize, f64) = match component_type {
+        5120usize => (1usize, 127f64),
+        5121usize => (1usize, 255f64),
+        5122usize => (2usize, 32767f64),
+        5123usize => (2usize, 65535f64),
+        5125usize => (4usize, 4294967295f64),
+        5126usize => (4usize, 1f64),
+        _ => return Err(ModelFileError::new("Unknown accessor component type"))
+    };
+    let normalize: bool = component_type != 5126usize && (normalize || accessor.get("normalized").and_then(| n | n.as_bool()).unwrap_or(false));
+
+    // Accessors without a view are all zeroes. Every primitive reads its vertices through data in the buffers, so one
+    // with more elements than there are bytes in them could never be used.
+    let Some(view) = accessor.get("bufferView").and_then(| v | v.as_usize()) else {
+        if count > buffers.iter().map(| b | b.len()).sum::<usize>() {
+            return Err(ModelFileError::new("Accessor without data is too long"));
+        }
+        return Ok((vec![0f64; count * components], components));
+    };
+    let (buffer, view_offset, view_length, stride) = buffer_view(doc, buffers, view)?;
+    let stride: usize = stride.unwrap_or(size * components);
+    // Checked before anything is allocated, as a bad count or offset could otherwise overflow or ask for any amount
+    let end: Option<usize> = match count {
+        0usize => Some(0usize),
+        _ => view_offset.checked_add(accessor.get("byteOffset").and_then(| o | o.as_usize()).unwrap_or(0usize))
+            .and_then(| offset | (count - 1usize).checked_mul(stride).and_then(| s | s.checked_add(offset)))
+            .and_then(| last | last.checked_add(size * components))
+    };
+    if end.is_none_or(| e | e > buffer.len().min(view_offset.saturating_add(view_length))) {
+        return Err(ModelFileError::new("Accessor outside of its buffer"));
+    }
+    let offset: usize = view_offset + accessor.get("byteOffset").and_then(| o | o.as_usize()).unwrap_or(0usize);
+
+    let mut ret: Vec<f64> = vec![0f64; count * components];
+    for (i, value) in ret.iter_mut().enumerate() {
+        let pos: usize = offset + (i / components) * stride + (i % components) * size;
+        let b: &[u8] = &buffer[pos .. pos + size];
+        let raw: f64 = match component_type {
+            5120usize => (b[0] as i8) as f64,
+            5121usize => b[0] as f64,
+            5122usize => i16::from_le_bytes([ b[0], b[1] ]) as f64,
+            5123usize => u16::from_le_bytes([ b[0], b[1] ]) as f64,
+            5125usize => u32::from_le_bytes([ b[0], b[1], b[2], b[3] ]) as f64,
+            _ => f32::from_le_bytes([ b[0], b[1], b[2], b[3] ]) as f64
+        };
+        *value = if normalize { (raw / max).max(-1f64) } else { raw };
+    }
+    Ok((ret, components))
+}
+
+fn read_primitive(doc: &Json, buffers: &[Vec<u8>], primitive: &Json, material: Option<&GltfMaterial>, chain: &[&Transform], out: &mut Vec<Triangle>) -> Result<(), ModelFileError> {
+    let attributes: &Json = primitive.get("attributes").ok_or_else(|| ModelFileError::new("Primitive without attributes"))?;
+    // Each attribute has to hold at least as many elements as there are positions, with at least a given number of
+    // components in each
+    let attribute = | name: &str, normalize: bool, components: usize, count: usize | -> Result<Option<(Vec<f64>, usize)>, ModelFileError> {
+        let Some(idx) = attributes.get(name).and_then(| a | a.as_usize()) else { return Ok(None); };
+        let (values, c) = read_accessor(doc, buffers, idx, normalize)?;
+        if c < components || values.len() / c < count {
+            return Err(ModelFileError::new(&format!("Attribute {} is too short", name)));
+        }
+        Ok(Some((values, c)))
+    };
+
+    let Some((positions, pc)) = attribute("POSITION", false, 3usize, 0usize)? else { return Ok(()); };
+    let vertex_count: usize = positions.len() / pc;
+    let normals: Option<(Vec<f64>, usize)> = attribute("NORMAL", false, 3usize, vertex_count)?;
+    let tex_coord: usize = material.and_then(| m | m.texture).map_or(0usize, | (_, set) | set);
+    let uvs: Option<(Vec<f64>, usize)> = attribute(&format!("TEXCOORD_{}", tex_coord), true, 2usize, vertex_count)?;
+    let colors: Option<(Vec<f64>, usize)> = attribute("COLOR_0", true, 3usize, vertex_count)?;
+    let factor: [f32; 4] = material.map_or([ 1f32; 4 ], | m | m.factor);
+    let texture: Option<usize> = material.and_then(| m | m.texture).map(| (idx, _) | idx);
+
+    let indices: Vec<usize> = match primitive.get("indices").and_then(| i | i.as_usize()) {
+        Some(idx) => read_accessor(doc, buffers, idx, false)?.0.iter().map(| i | *i as usize).collect(),
+        None => (0 .. vertex_count).collect()
+    };
+    if let Some(bad) = indices.iter().find(| i | **i >= vertex_count) {
+        return Err(ModelFileError::new(&format!("Primitive uses missing vertex {}", bad)));
+    }
+
+    let corner = | i: usize | -> Corner {
+        let mut position: Vector3D = Vector3D::new(positions[i * pc] as f32, positions[i * pc + 1usize] as f32, positions[i * pc + 2usize] as f32);
+        for transform in chain.iter() {
+            transform.transform_vector(&mut position);
+        }
+        position.z = -position.z;
+
+        let normal: Option<Vector3D> = normals.as_ref().map(| (n, c) | {
+            let mut ret: Vector3D = Vector3D::new(n[i * c] as f32, n[i * c + 1usize] as f32, n[i * c + 2usize] as f32);
+            for transform in chain.iter() {
+                transform.transform_normal(&mut ret);
+            }
+            ret.z = -ret.z;
+            ret.normalize();
+            ret
+        });
+
+        let uv: (f32, f32) = uvs.as_ref().map_or((0f32, 0f32), | (t, c) | (t[i * c] as f32, t[i * c + 1usize] as f32));
+
+        // Factors and vertex colors are both linear, while face colors are stored encoded
+        let mut linear: [f32; 4] = factor;
+        if let Some((col, c)) = colors.as_ref() {
+            for (channel, value) in linear.iter_mut().enumerate().take(*c) {
+                *value *= col[i * c + channel] as f32;
+            }
+        }
+        let color: RGBA8 = ColorSpace::Linear.encode(&RGBA8F { r: linear[0] * 255f32, g: linear[1] * 255f32, b: linear[2] * 255f32, a: linear[3] * 255f32 });
+
+        Corner { position, normal, uv, color }
+    };
+
+    let mut add = | a: usize, b: usize, c: usize | {
+        if a == b || b == c || a == c { return; }
+        out.push(Triangle { corners: [ corner(a), corner(b), corner(c) ], texture });
+    };
+    match primitive.get("mode").and_then(| m | m.as_usize()).unwrap_or(4usize) {
+        4usize => for t in indices.chunks_exact(3usize) { add(t[0], t[1], t[2]); },
+        // Every other triangle of a strip runs the other way, so it is flipped back
+        5usize => for i in 2usize .. indices.len() {
+            if i % 2usize == 0usize { add(indices[i - 2usize], indices[i - 1usize], indices[i]); } else { add(indices[i - 1usize], indices[i - 2usize], indices[i]); }
+        },
+        6usize => for i in 2usize .. indices.len() { add(indices[0], indices[i - 1usize], indices[i]); },
+        _ => { } // points and lines have no faces to draw
+    }
+    Ok(())
+}
+
+// Local transform of a node, from either its matrix or its translation, rotation and scale
+fn node_transform(node: &Json) -> Transform {
+    let mut ret: Transform = Transform::new();
+    if let Some(m) = node.get("matrix").and_then(| m | m.as_floats::<16>()) {
+        // Column major, with the columns of the upper 3x3 scaled by the scale
+        let mut columns: [Vector3D; 3] = [
+            Vector3D::new(m[0], m[1], m[2]),
+            Vector3D::new(m[4], m[5], m[6]),
+            Vector3D::new(m[8], m[9], m[10])
+        ];
+        let mut scale: [f32; 3] = [ columns[0].norm(), columns[1].norm(), columns[2].norm() ];
+        if columns[0].cross(&columns[1]).dot(&columns[2]) < 0f32 {
+            scale[0] = -scale[0];
+        }
+        for (column, s) in columns.iter_mut().zip(scale) {
+            if s != 0f32 { column.divide_scalar(s); }
+        }
+        ret.translation = Vector3D::new(m[12], m[13], m[14]);
+        ret.rotation = rotation_from_columns(&columns);
+        ret.scale = Vector3D::new(scale[0], scale[1], scale[2]);
+        return ret;
+    }
+
+    if let Some(t) = node.get("translation").and_then(| t | t.as_floats::<3>()) {
+        ret.translation = Vector3D::new(t[0], t[1], t[2]);
+    }
+    if let Some(r) = node.get("rotation").and_then(| r | r.as_floats::<4>()) {
+        // Stored as x, y, z, w
+        let mut rotation: Quaternion = Quaternion::new(r[3], r[0], r[1], r[2]);
+        rotation.normalize();
+        ret.rotation = rotation;
+    }
+    if let Some(s) = node.get("scale").and_then(| s | s.as_floats::<3>()) {
+        ret.scale = Vector3D::new(s[0], s[1], s[2]);
+    }
+    ret
+}
+
+// Quaternion of a rotation matrix given as its columns, taking the root of whichever term is largest for precision
+fn rotation_from_columns(c: &[Vector3D; 3]) -> Quaternion {
+    let (m00, m01, m02) = (c[0].x, c[1].x, c[2].x);
+    let (m10, m11, m12) = (c[0].y, c[1].y, c[2].y);
+    let (m20, m21, m22) = (c[0].z, c[1].z, c[2].z);
+    let trace: f32 = m00 + m11 + m22;
+    let mut ret: Quaternion = if trace > 0f32 {
+        let s: f32 = 0.5f32 / (trace + 1f32).sqrt();
+        Quaternion::new(0.25f32 / s, (m21 - m12) * s, (m02 - m20) * s, (m10 - m01) * s)
+    } else if m00 > m11 && m00 > m22 {
+        let s: f32 = 2f32 * (1f32 + m00 - m11 - m22).sqrt();
+        Quaternion::new((m21 - m12) / s, 0.25f32 * s, (m01 + m10) / s, (m02 + m20) / s)
+    } else if m11 > m22 {
+        let s: f32 = 2f32 * (1f32 + m11 - m00 - m22).sqrt();
+        Quaternion::new((m02 - m20) / s, (m01 + m10) / s, 0.25f32 * s, (m12 + m21) / s)
+    } else {
+        let s: f32 = 2f32 * (1f32 + m22 - m00 - m11).sqrt();
+        Quaternion::new((m10 - m01) / s, (m02 + m20) / s, (m12 + m21) / s, 0.25f32 * s)
+    };
+    ret.normalize();
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::testing::assert_close;
+    use std::io::Cursor;
+    use image::{ImageOutputFormat, Rgba, RgbaImage};
+    use crate::model::testing::load_bytes;
+
+    const BASE64: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    fn encode_base64(bytes: &[u8]) -> String {
+        let mut ret: String = String::new();
+        for chunk in bytes.chunks(3usize) {
+            let n: u32 = chunk.iter().enumerate().fold(0u32, | acc, (i, b) | acc | ((*b as u32) << (16usize - i * 8usize)));
+            for i in 0 .. 4usize {
+                ret.push(if i <= chunk.len() { BASE64[((n >> (18usize - i * 6usize)) & 63u32) as usize] as char } else { '=' });
+            }
+        }
+        ret
+    }
+
+    fn floats(values: &[f32]) -> Vec<u8> {
+        values.iter().flat_map(| v | v.to_le_bytes()).collect()
+    }
+
+    // Writes a document whose only buffer is embedded, and loads it
+    fn load_doc(json: &str, buffer: &[u8]) -> Result<Vec<Model>, ModelFileError> {
+        let json: String = json.replace("BUFFER", &format!("data:application/octet-stream;base64,{}", encode_base64(buffer)));
+        load_bytes("gltf", json.as_bytes(), load_gltf)
+    }
+
+    // A triangle in the z = 0 plane, counter-clockwise seen from +z, with an accessor that can be swapped out
+    fn triangle_doc(accessor: &str) -> String {
+        format!(r#"{{
+            "asset": {{ "version": "2.0" }},
+            "buffers": [ {{ "uri": "BUFFER", "byteLength": 36 }} ],
+            "bufferViews": [ {{ "buffer": 0, "byteLength": 36 }} ],
+            "accessors": [ {} ],
+            "meshes": [ {{ "primitives": [ {{ "attributes": {{ "POSITION": 0 }} }} ] }} ],
+            "nodes": [ {{ "mesh": 0 }} ]
+        }}"#, accessor)
+    }
+
+    const TRIANGLE: [f32; 9] = [ 0f32, 0f32, 0f32, 1f32, 0f32, 0f32, 0f32, 1f32, 0f32 ];
+    const TRIANGLE_ACCESSOR: &str = r#"{ "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3" }"#;
+
+    #[test]
+    fn decodes_base64() {
+        assert_eq!(decode_base64("SGVsbG8="), Some(b"Hello".to_vec()));
+        assert_eq!(decode_base64("SGVsbG8"), Some(b"Hello".to_vec()));
+        assert_eq!(decode_base64("SGVs\r\nbG8h"), Some(b"Hello!".to_vec()));
+        // The URL safe alphabet is read too
+        assert_eq!(decode_base64("-_-_"), Some(vec![ 0xfbu8, 0xffu8, 0xbfu8 ]));
+        assert_eq!(decode_base64("+/+/"), Some(vec![ 0xfbu8, 0xffu8, 0xbfu8 ]));
+        assert_eq!(decode_base64(""), Some(Vec::new()));
+        assert_eq!(decode_base64("SGV*bG8="), None);
+        let bytes: Vec<u8> = (0u8 ..= 255u8).collect();
+        assert_eq!(decode_base64(&encode_base64(&bytes)), Some(bytes));
+    }
+
+    #[test]
+    fn rotation_from_columns_covers_every_branch() {
+        // No turn keeps the trace positive, while half turns about each axis make that axis' term the largest
+        let cases: [(Vector3D, f32); 4] = [
+            (Vector3D::new(0.3f32, 0.8f32, -0.5f32), 0.7f32),
+            (Vector3D::new(1f32, 0.1f32, 0f32), 3f32),
+            (Vector3D::new(0f32, 1f32, 0.1f32), 3f32),
+            (Vector3D::new(0.1f32, 0f32, 1f32), 3f32)
+        ];
+        for (mut axis, angle) in cases {
+            axis.normalize();
+            let s: f32 = (angle / 2f32).sin();
+            let rotation: Quaternion = Quaternion::new((angle / 2f32).cos(), axis.x * s, axis.y * s, axis.z * s);
+            let columns: [Vector3D; 3] = [
+                rotation.rotate(&Vector3D::new(1f32, 0f32, 0f32)),
+                rotation.rotate(&Vector3D::new(0f32, 1f32, 0f32)),
+                rotation.rotate(&Vector3D::new(0f32, 0f32, 1f32))
+            ];
+            let found: Quaternion = rotation_from_columns(&columns);
+            for v in [ Vector3D::new(1f32, 2f32, 3f32), Vector3D::new(-0.5f32, 0f32, 1f32) ] {
+                assert_close(&found.rotate(&v), &rotation.rotate(&v));
+            }
+        }
+    }
+
+    #[test]
+    fn node_matrix_matches_translation_rotation_and_scale() {
+        let trs: Json = Json::parse(r#"{ "translation": [ 1, 2, 3 ], "rotation": [ 0, 0.38268343, 0, 0.92387953 ], "scale": [ 2, 0.5, 1.5 ] }"#).unwrap();
+        let from_trs: Transform = node_transform(&trs);
+
+        // Columns of the same transform, each axis scaled then rotated, with the translation last
+        let columns: Vec<Vector3D> = [ 2f32, 0.5f32, 1.5f32 ].iter().enumerate().map(| (i, s) | {
+            let mut axis: Vector3D = Vector3D::zero();
+            axis.set_components(&[ 0f32, 1f32, 2f32 ].map(| c | if c as usize == i { *s } else { 0f32 }));
+            from_trs.rotation.rotate(&axis)
+        }).collect();
+        let mut values: Vec<String> = Vec::new();
+        for c in columns.iter() {
+            values.extend([ c.x, c.y, c.z, 0f32 ].iter().map(| v | v.to_string()));
+        }
+        values.extend([ "1", "2", "3", "1" ].map(String::from));
+        let matrix: Json = Json::parse(&format!(r#"{{ "matrix": [ {} ] }}"#, values.join(", "))).unwrap();
+        let from_matrix: Transform = node_transform(&matrix);
+
+        for point in [ Vector3D::zero(), Vector3D::new(1f32, 0f32, 0f32), Vector3D::new(-0.5f32, 2f32, 0.25f32) ] {
+            let mut a: Vector3D = point.copy();
+            let mut b: Vector3D = point.copy();
+            from_trs.transform_vector(&mut a);
+            from_matrix.transform_vector(&mut b);
+            assert_close(&a, &b);
+        }
+    }
+
+    #[test]
+    fn node_matrix_keeps_mirroring() {
+        let matrix: Json = Json::parse(r#"{ "matrix": [ -1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1 ] }"#).unwrap();
+        let mut v: Vector3D = Vector3D::new(1f32, 2f32, 3f32);
+        node_transform(&matrix).transform_vector(&mut v);
+        assert_close(&v, &Vector3D::new(-1f32, 2f32, 3f32));
+    }
+
+    #[test]
+    fn loads_embedded_triangle() {
+        let models: Vec<Model> = load_doc(&triangle_doc(TRIANGLE_ACCESSOR), &floats(&TRIANGLE)).unwrap();
+        assert_eq!(models.len(), 1usize);
+        assert_eq!(models[0].get_face_count(), 1usize);
+        // Flipped into blot's space, the front faces the camera
+        assert!(models[0].get_face(0usize).get_normal().z < -0.99f32);
+    }
+
+    #[test]
+    fn rejects_accessors_outside_their_buffer() {
+        let bad: [&str; 4] = [
+            r#"{ "bufferView": 0, "componentType": 5126, "count": 4, "type": "VEC3" }"#,
+            r#"{ "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3", "byteOffset": 18446744073709551615 }"#,
+            r#"{ "bufferView": 0, "componentType": 5126, "count": 4611686018427387904, "type": "MAT4" }"#,
+            r#"{ "componentType": 5126, "count": 1152921504606846976, "type": "VEC3" }"#
+        ];
+        for accessor in bad {
+            assert!(load_doc(&triangle_doc(accessor), &floats(&TRIANGLE)).is_err(), "{}", accessor);
+        }
+    }
+
+    #[test]
+    fn splits_textures_into_models_that_keep_their_uvs() {
+        let mut png: Vec<u8> = Vec::new();
+        RgbaImage::from_pixel(2u32, 2u32, Rgba([ 255u8, 0u8, 0u8, 255u8 ])).write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png).unwrap();
+        let mut buffer: Vec<u8> = floats(&TRIANGLE);
+        buffer.extend(floats(&[ 0f32, 0f32, 3f32, 0f32, 0f32, 3f32 ]));
+        let json: String = r#"{
+            "asset": { "version": "2.0" },
+            "buffers": [ { "uri": "BUFFER", "byteLength": 60 } ],
+            "bufferViews": [ { "buffer": 0, "byteLength": 36 }, { "buffer": 0, "byteOffset": 36, "byteLength": 24 } ],
+            "accessors": [
+                { "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3" },
+                { "bufferView": 1, "componentType": 5126, "count": 3, "type": "VEC2" }
+            ],
+            "images": [ { "uri": "IMAGE" } ],
+            "samplers": [ { "wrapS": 33071, "wrapT": 33648 } ],
+            "textures": [ { "source": 0 }, { "source": 0, "sampler": 0 } ],
+            "materials": [
+                { "pbrMetallicRoughness": { "baseColorTexture": { "index": 0 } } },
+                { "pbrMetallicRoughness": { "baseColorTexture": { "index": 1 } } },
+                { "pbrMetallicRoughness": { "baseColorFactor": [ 1, 0, 0, 1 ] } }
+            ],
+            "meshes": [ { "primitives": [
+                { "attributes": { "POSITION": 0, "TEXCOORD_0": 1 }, "material": 0 },
+                { "attributes": { "POSITION": 0, "TEXCOORD_0": 1 }, "material": 2 },
+                { "attributes": { "POSITION": 0, "TEXCOORD_0": 1 }, "material": 1 },
+                { "attributes": { "POSITION": 0, "TEXCOORD_0": 1 }, "material": 0 }
+            ] } ],
+            "nodes": [ { "mesh": 0 } ]
+        }"#.replace("IMAGE", &format!("data:image/png;base64,{}", encode_base64(&png)));
+        let models: Vec<Model> = load_doc(&json, &buffer).unwrap();
+        assert_eq!(models.iter().map(| m | m.get_face_count()).collect::<Vec<usize>>(), vec![ 2usize, 1usize, 1usize ]);
+        // Both primitives with the first texture use the same three corners
+        assert_eq!(models[0].get_vertex_count(), 3usize);
+
+        // UVs past the edge are kept for the sampler to repeat
+        let uvs: Vec<(f32, f32)> = models[0].get_face_polygon(0usize, false).iter().map(| c | c.uv).collect();
+        assert!(uvs.contains(&(3f32, 0f32)) && uvs.contains(&(0f32, 3f32)));
+        let wrap: TextureWrap = models[0].material.wrap.unwrap();
+        assert!(matches!((wrap.u, wrap.v), (WrapMode::Repeat, WrapMode::Repeat)));
+        let wrap: TextureWrap = models[2].material.wrap.unwrap();
+        assert!(matches!((wrap.u, wrap.v), (WrapMode::ClampToEdge, WrapMode::MirroredRepeat)));
+
+        // The untextured part is colored by its faces, on white
+        assert!(models[1].material.wrap.is_none());
+        assert_eq!(models[1].get_face_polygon(0usize, false)[0].color.unwrap().g, 0u8);
+    }
+
+}
@@ -0,0 +1,278 @@
+use crate::model::ModelFileError;
+
+// A parsed JSON value, just enough for the model formats that describe themselves in JSON
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>) // in file order
+}
+
+impl Json {
+
+    pub fn parse(text: &str) -> Result<Json, ModelFileError> {
+        let mut parser: JsonParser = JsonParser { bytes: text.as_bytes(), pos: 0usize };
+        let ret: Json = parser.parse_value(0usize)?;
+        parser.skip_whitespace();
+        if parser.pos != parser.bytes.len() {
+            return Err(parser.error());
+        }
+        return Ok(ret);
+    }
+
+    // Member of an object, if this is one and it has the key
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        if let Json::Object(members) = self {
+            return members.iter().find(| (k, _) | k == key).map(| (_, v) | v);
+        }
+        return None;
+    }
+
+    pub fn as_array(&self) -> &[Json] {
+        if let Json::Array(items) = self {
+            return items;
+        }
+        return &[];
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        if let Json::Number(n) = self {
+            return Some(*n);
+        }
+        return None;
+    }
+
+    pub fn as_usize(&self) -> Option<usize> {
+        return self.as_f64().filter(| n | *n >= 0f64 && n.fract() == 0f64).map(| n | n as usize);
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        if let Json::String(s) = self {
+            return Some(s);
+        }
+        return None;
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        if let Json::Bool(b) = self {
+            return Some(*b);
+        }
+        return None;
+    }
+
+    // Numbers of an array, if it has exactly N of them
+    pub fn as_floats<const N: usize>(&self) -> Option<[f32; N]> {
+        let items: &[Json] = self.as_array();
+        if items.len() != N { return None; }
+        let mut ret: [f32; N] = [ 0f32; N ];
+        for (i, item) in items.iter().enumerate() {
+            ret[i] = item.as_f64()? as f32;
+        }
+        return Some(ret);
+    }
+
+}
+
+// Nesting past this is refused rather than risking the stack
+const MAX_DEPTH: usize = 256usize;
+
+struct JsonParser<'a> {
+    bytes: &'a [u8],
+    pos: usize
+}
+
+impl JsonParser<'_> {
+
+    fn error(&self) -> ModelFileError {
+        return ModelFileError::new(&format!("Invalid JSON at byte {}", self.pos));
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.bytes.len() && matches!(self.bytes[self.pos], b' ' | b'\t' | b'\n' | b'\r') {
+            self.pos += 1usize;
+        }
+    }
+
+    fn expect(&mut self, literal: &[u8]) -> Result<(), ModelFileError> {
+        if !self.bytes[self.pos ..].starts_with(literal) {
+            return Err(self.error());
+        }
+        self.pos += literal.len();
+        return Ok(());
+    }
+
+    fn parse_value(&mut self, depth: usize) -> Result<Json, ModelFileError> {
+        if depth > MAX_DEPTH {
+            return Err(self.error());
+        }
+        self.skip_whitespace();
+        return match self.bytes.get(self.pos) {
+            Some(b'{') => {
+                self.pos += 1usize;
+                let mut members: Vec<(String, Json)> = Vec::new();
+                self.skip_whitespace();
+                if self.bytes.get(self.pos) == Some(&b'}') {
+                    self.pos += 1usize;
+                    return Ok(Json::Object(members));
+                }
+                loop {
+                    self.skip_whitespace();
+                    let key: String = self.parse_string()?;
+                    self.skip_whitespace();
+                    self.expect(b":")?;
+                    members.push((key, self.parse_value(depth + 1usize)?));
+                    self.skip_whitespace();
+                    match self.bytes.get(self.pos) {
+                        Some(b',') => self.pos += 1usize,
+                        Some(b'}') => { self.pos += 1usize; break; },
+                        _ => return Err(self.error())
+                    }
+                }
+                Ok(Json::Object(members))
+            },
+            Some(b'[') => {
+                self.pos += 1usize;
+                let mut items: Vec<Json> = Vec::new();
+                self.skip_whitespace();
+                if self.bytes.get(self.pos) == Some(&b']') {
+                    self.pos += 1usize;
+                    return Ok(Json::Array(items));
+                }
+                loop {
+                    items.push(self.parse_value(depth + 1usize)?);
+                    self.skip_whitespace();
+                    match self.bytes.get(self.pos) {
+                        Some(b',') => self.pos += 1usize,
+                        Some(b']') => { self.pos += 1usize; break; },
+                        _ => return Err(self.error())
+                    }
+                }
+                Ok(Json::Array(items))
+            },
+            Some(b'"') => Ok(Json::String(self.parse_string()?)),
+            Some(b't') => self.expect(b"true").map(| _ | Json::Bool(true)),
+            Some(b'f') => self.expect(b"false").map(| _ | Json::Bool(false)),
+            Some(b'n') => self.expect(b"null").map(| _ | Json::Null),
+            Some(b'-' | b'0' ..= b'9') => {
+                let start: usize = self.pos;
+                while self.pos < self.bytes.len() && matches!(self.bytes[self.pos], b'-' | b'+' | b'.' | b'e' | b'E' | b'0' ..= b'9') {
+                    self.pos += 1usize;
+                }
+                std::str::from_utf8(&self.bytes[start .. self.pos]).ok()
+                    .and_then(| s | s.parse::<f64>().ok())
+                    .map(Json::Number)
+                    .ok_or_else(|| self.error())
+            },
+            _ => Err(self.error())
+        };
+    }
+
+    fn parse_string(&mut self) -> Result<String, ModelFileError> {
+        self.expect(b"\"")?;
+        let mut ret: Vec<u8> = Vec::new();
+        loop {
+            let b: u8 = *self.bytes.get(self.pos).ok_or_else(|| self.error())?;
+            self.pos += 1usize;
+            match b {
+                b'"' => break,
+                b'\\' => {
+                    let escape: u8 = *self.bytes.get(self.pos).ok_or_else(|| self.error())?;
+                    self.pos += 1usize;
+                    let c: char = match escape {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => {
+                            let mut code: u32 = self.parse_hex4()?;
+                            // Characters outside the basic plane come as a pair of surrogates
+                            if (0xd800u32 .. 0xdc00u32).contains(&code) && self.bytes[self.pos ..].starts_with(b"\\u") {
+                                self.pos += 2usize;
+                                let low: u32 = self.parse_hex4()?;
+                                code = 0x10000u32 + ((code - 0xd800u32) << 10) + (low.wrapping_sub(0xdc00u32) & 0x3ffu32);
+                            }
+                            char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER)
+                        },
+                        _ => return Err(self.error())
+                    };
+                    let mut buf: [u8; 4] = [ 0u8; 4 ];
+                    ret.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                },
+                _ => ret.push(b)
+            }
+        }
+        return String::from_utf8(ret).map_err(| _ | self.error());
+    }
+
+    fn parse_hex4(&mut self) -> Result<u32, ModelFileError> {
+        let digits: &[u8] = self.bytes.get(self.pos .. self.pos + 4usize).ok_or_else(|| self.error())?;
+        let ret: u32 = std::str::from_utf8(digits).ok()
+            .and_then(| s | u32::from_str_radix(s, 16).ok())
+            .ok_or_else(|| self.error())?;
+        self.pos += 4usize;
+        return Ok(ret);
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_nested_values() {
+        let doc: Json = Json::parse(" { \"a\": [ 1, -2.5e1, true, null ], \"b\": { \"c\": \"d\" } } ").unwrap();
+        let a: &[Json] = doc.get("a").unwrap().as_array();
+        assert_eq!(a.len(), 4usize);
+        assert_eq!(a[0].as_usize(), Some(1usize));
+        assert_eq!(a[1].as_f64(), Some(-25f64));
+        assert_eq!(a[1].as_usize(), None);
+        assert_eq!(a[2].as_bool(), Some(true));
+        assert!(matches!(a[3], Json::Null));
+        assert_eq!(doc.get("b").and_then(| b | b.get("c")).and_then(| c | c.as_str()), Some("d"));
+        assert!(doc.get("e").is_none());
+    }
+
+    #[test]
+    fn decodes_escapes() {
+        let doc: Json = Json::parse(r#""q\" s\\ f\/ \b\f\n\r\t \u00e9 ü""#).unwrap();
+        assert_eq!(doc.as_str(), Some("q\" s\\ f/ \u{8}\u{c}\n\r\t é ü"));
+        assert!(Json::parse(r#""\x""#).is_err());
+        assert!(Json::parse(r#""\u12""#).is_err());
+        assert!(Json::parse("\"open").is_err());
+    }
+
+    #[test]
+    fn joins_surrogate_pairs() {
+        let doc: Json = Json::parse(r#""\ud83d\ude00 \uD834\uDD1E""#).unwrap();
+        assert_eq!(doc.as_str(), Some("\u{1f600} \u{1d11e}"));
+        // A lone half of a pair can't be a character of its own
+        let lone: Json = Json::parse(r#""\ud83d!""#).unwrap();
+        assert_eq!(lone.as_str(), Some("\u{fffd}!"));
+    }
+
+    #[test]
+    fn limits_nesting_depth() {
+        let nested = | depth: usize | format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        assert!(Json::parse(&nested(MAX_DEPTH)).is_ok());
+        assert!(Json::parse(&nested(MAX_DEPTH + 2usize)).is_err());
+        assert!(Json::parse(&nested(100000usize)).is_err());
+    }
+
+    #[test]
+    fn rejects_trailing_garbage() {
+        assert!(Json::parse("{} \n").is_ok());
+        assert!(Json::parse("{} x").is_err());
+        assert!(Json::parse("[1, 2] ]").is_err());
+        assert!(Json::parse("[1, 2,]").is_err());
+        assert!(Json::parse("{\"a\": 1,}").is_err());
+        assert!(Json::parse("").is_err());
+    }
+
+}
@@ -0,0 +1,32 @@
+use image::{DynamicImage, Rgba, RgbaImage};
+use crate::display::canvas::{BlendMode, ImageCanvas, TextureWrap, RGBA8};
+
+// Shown on models that come without any colors or UVs of their own
+pub const UNTEXTURED_COLOR: RGBA8 = RGBA8 { r: 180u8, g: 190u8, b: 205u8, a: 255u8 };
+
+// Surface properties shared by every face of a model
+pub struct Material {
+    pub blend: BlendMode,
+    pub texture: Option<ImageCanvas>, // used when no other texture is given, such as a palette from a model file
+    pub wrap: Option<TextureWrap> // how textures wrap on this model, such as a file's sampler
+}
+
+impl Material {
+
+    pub fn new() -> Self {
+        return Self { blend: BlendMode::Replace, texture: None, wrap: None };
+    }
+
+    // Shows a single color when no other texture is given, for models without UVs
+    pub fn set_color(&mut self, color: &RGBA8) {
+        let image: RgbaImage = RgbaImage::from_pixel(1u32, 1u32, Rgba::<u8>::from([ color.r, color.g, color.b, color.a ]));
+        self.texture = Some(ImageCanvas::new(DynamicImage::ImageRgba8(image)));
+    }
+
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use crate::model::{Model, ModelFileError};
+
+// Writes models together as one Wavefront OBJ file, with a position, UV and (if the face has them) normal at each
+// corner. Equal values are shared between corners. OBJ is right-handed with y up, so z is flipped and the winding
+// reversed to keep the models looking as they do in blot. Each model's transform is applied if bake is set.
+pub fn save_obj<P: AsRef<Path>>(models: &[&Model], path: P, bake: bool) -> Result<(), ModelFileError> {
+    let file: File = File::create(path).map_err(| err | ModelFileError::new(&format!("{}", err)))?;
+    let mut out: BufWriter<File> = BufWriter::new(file);
+    write_obj(models, &mut out, bake).map_err(| err | ModelFileError::new(&format!("{}", err)))
+}
+
+fn write_obj(models: &[&Model], out: &mut dyn Write, bake: bool) -> std::io::Result<()> {
+    writeln!(out, "# blot")?;
+
+    let mut positions: HashMap<[u32; 3], usize> = HashMap::new();
+    let mut uvs: HashMap<[u32; 2], usize> = HashMap::new();
+    let mut normals: HashMap<[u32; 3], usize> = HashMap::new();
+    let mut faces: Vec<String> = Vec::with_capacity(models.iter().map(| m | m.get_face_count()).sum());
+    for polygon in models.iter().flat_map(| m | (0 .. m.get_face_count()).map(| idx | m.get_face_polygon(idx, bake))) {
+        if polygon.len() < 3usize { continue; }
+
+        let mut face: String = String::from("f");
+        for corner in polygon.iter().rev() {
+            let p: [f32; 3] = [ corner.position.x, corner.position.y, -corner.position.z ];
+            let v: usize = pool_index(&mut positions, p, "v", out)?;
+            // OBJ measures v up from the bottom of the texture
+            let vt: usize = pool_index(&mut uvs, [ corner.uv.0, 1f32 - corner.uv.1 ], "vt", out)?;
+            match &corner.normal {
+                Some(n) => {
+                    let vn: usize = pool_index(&mut normals, [ n.x, n.y, -n.z ], "vn", out)?;
+                    face.push_str(&format!(" {}/{}/{}", v, vt, vn));
+                },
+                None => face.push_str(&format!(" {}/{}", v, vt))
+            }
+        }
+        faces.push(face);
+    }
+
+    for face in faces.iter() {
+        writeln!(out, "{}", face)?;
+    }
+    out.flush()
+}
+
+// One based index of a value in its pool, writing it out under tag the first time it is seen
+fn pool_index<const N: usize>(pool: &mut HashMap<[u32; N], usize>, value: [f32; N], tag: &str, out: &mut dyn Write) -> std::io::Result<usize> {
+    let key: [u32; N] = value.map(| c | c.to_bits());
+    if let Some(idx) = pool.get(&key) {
+        return Ok(*idx);
+    }
+
+    write!(out, "{}", tag)?;
+    for c in value {
+        write!(out, " {}", c)?;
+    }
+    writeln!(out)?;
+    let idx: usize = pool.len() + 1usize;
+    pool.insert(key, idx);
+    Ok(idx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::vector::Vector3D;
+    use crate::model::platonic::{cube, CubeLayout};
+
+    fn write(models: &[&Model], bake: bool) -> String {
+        let mut out: Vec<u8> = Vec::new();
+        write_obj(models, &mut out, bake).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn count(text: &str, tag: &str) -> usize {
+        text.lines().filter(| l | l.split(' ').next() == Some(tag)).count()
+    }
+
+    #[test]
+    fn writes_every_model_and_bakes_their_transforms() {
+        let first: Model = cube(CubeLayout::Same);
+        let mut second: Model = cube(CubeLayout::Same);
+        second.transform.translate(&Vector3D::new(5f32, 0f32, 2f32));
+
+        // Left in place, the second cube lands on the first and shares its vertices
+        let stacked: String = write(&[ &first, &second ], false);
+        assert_eq!(count(&stacked, "f"), 12usize);
+        assert_eq!(count(&stacked, "v"), 8usize);
+
+        let baked: String = write(&[ &first, &second ], true);
+        assert_eq!(count(&baked, "f"), 12usize);
+        assert_eq!(count(&baked, "v"), 16usize);
+        // z is flipped on the way out
+        assert!(baked.lines().any(| l | l == "v 6 1 -3"));
+    }
+
+}
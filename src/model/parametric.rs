@@ -0,0 +1,103 @@
+use crate::model::Model;
+use crate::math::vector::Vector3D;
+use crate::math::VectorLike;
+
+// Surface traced by a function of u and v, both running from 0 to 1, and split into a grid of quads with matching UVs.
+// The front of the surface is the side that df/du x df/dv points to, which is the side that won't be culled.
+pub struct ParametricSurface<'a> {
+    f: Box<dyn Fn(f32, f32) -> Vector3D + 'a>,
+    normal: Option<Box<dyn Fn(f32, f32) -> Vector3D + 'a>>,
+    u_steps: u16,
+    v_steps: u16,
+    wrap_u: bool,
+    wrap_v: bool
+}
+
+impl<'a> ParametricSurface<'a> {
+
+    pub fn new(f: impl Fn(f32, f32) -> Vector3D + 'a, u_steps: u16, v_steps: u16) -> Self {
+        return Self {
+            f: Box::new(f),
+            normal: None,
+            u_steps: u_steps.max(1u16),
+            v_steps: v_steps.max(1u16),
+            wrap_u: false,
+            wrap_v: false
+        };
+    }
+
+    // Closes the surface along u or v, reusing the points at 0 for 1 so that the seam can't crack open
+    pub fn wrap(mut self, u: bool, v: bool) -> Self {
+        self.wrap_u = u;
+        self.wrap_v = v;
+        self
+    }
+
+    // Shades the surface smoothly with the given normal at each vertex, instead of flat faces
+    pub fn normals(mut self, normal: impl Fn(f32, f32) -> Vector3D + 'a) -> Self {
+        self.normal = Some(Box::new(normal));
+        self
+    }
+
+    pub fn build(&self) -> Model {
+        let mut ret: Model = Model::expect((self.u_steps as usize) * (self.v_steps as usize));
+        self.build_into(&mut ret);
+        ret
+    }
+
+    // Adds the surface's faces to an existing model, such as to put caps on it. Neighbouring faces share their
+    // vertices and normals, while UVs are kept per grid point so that a wrapped seam still runs from 0 to 1.
+    pub fn build_into(&self, model: &mut Model) {
+        let cols: usize = (self.u_steps as usize) + 1usize;
+        let rows: usize = (self.v_steps as usize) + 1usize;
+
+        let mut points: Vec<Vector3D> = Vec::with_capacity(cols * rows);
+        let mut vertices: Vec<usize> = Vec::with_capacity(cols * rows);
+        let mut normals: Vec<usize> = Vec::new();
+        let mut uvs: Vec<usize> = Vec::with_capacity(cols * rows);
+        for j in 0 .. rows {
+            let v: f32 = (j as f32) / (self.v_steps as f32);
+            for i in 0 .. cols {
+                let u: f32 = (i as f32) / (self.u_steps as f32);
+                uvs.push(model.add_uv(u, v));
+
+                // The far edge of a wrapped surface is the near one again
+                let si: usize = if self.wrap_u && i == cols - 1usize { 0usize } else { i };
+                let sj: usize = if self.wrap_v && j == rows - 1usize { 0usize } else { j };
+                if si != i || sj != j {
+                    let same: usize = sj * cols + si;
+                    points.push(points[same].copy());
+                    vertices.push(vertices[same]);
+                    if !normals.is_empty() { normals.push(normals[same]); }
+                    continue;
+                }
+
+                // Shared so that faces added around the surface afterwards, such as walls, meet its vertices
+                let point: Vector3D = (self.f)(u, v);
+                vertices.push(model.shared_vertex(point.copy()));
+                points.push(point);
+                if let Some(normal) = self.normal.as_ref() {
+                    let mut n: Vector3D = normal(u, v);
+                    n.normalize();
+                    normals.push(model.add_normal(n));
+                }
+            }
+        }
+
+        for j in 0 .. (rows - 1usize) {
+            for i in 0 .. (cols - 1usize) {
+                let corners: [usize; 4] = [ j * cols + i, j * cols + i + 1usize, (j + 1usize) * cols + i, (j + 1usize) * cols + i + 1usize ];
+
+                // Quads pinched to nothing along both edges, such as between two poles, would only be skipped over
+                if points[corners[0]] == points[corners[1]] && points[corners[2]] == points[corners[3]] { continue; }
+
+                model.add_indexed_face(corners.map(| c | vertices[c]), corners.map(| c | uvs[c]));
+                if !normals.is_empty() {
+                    let idx: usize = model.get_face_count() - 1usize;
+                    model.set_face_normal_indices(idx, corners.map(| c | normals[c]));
+                }
+            }
+        }
+    }
+
+}
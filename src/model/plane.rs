@@ -0,0 +1,54 @@
+use crate::model::Model;
+use crate::math::quad::Quad;
+use crate::math::vector::Vector3D;
+
+// Flat grid in the xz plane, centered on the origin. Both sides are built, since a one sided plane would be culled
+// for half of every spin, and each side is mapped so that the texture reads the right way round.
+pub fn plane(width: f32, depth: f32, mut cols: u16, mut rows: u16) -> Model {
+    if cols < 1u16 { cols = 1u16; }
+    if rows < 1u16 { rows = 1u16; }
+
+    let mut ret: Model = Model::expect((cols as usize) * (rows as usize) * 2usize);
+    for row in 0 .. rows {
+        let v1: f32 = (row as f32) / (rows as f32);
+        let v2: f32 = ((row + 1u16) as f32) / (rows as f32);
+        let z1: f32 = (0.5f32 - v1) * depth;
+        let z2: f32 = (0.5f32 - v2) * depth;
+        for col in 0 .. cols {
+            let u1: f32 = (col as f32) / (cols as f32);
+            let u2: f32 = ((col + 1u16) as f32) / (cols as f32);
+            let x1: f32 = (u1 - 0.5f32) * width;
+            let x2: f32 = (u2 - 0.5f32) * width;
+
+            // Top, facing +y
+            ret.add_face_uv(
+                Quad {
+                    tl: Vector3D::new(x1, 0f32, z1),
+                    tr: Vector3D::new(x2, 0f32, z1),
+                    bl: Vector3D::new(x1, 0f32, z2),
+                    br: Vector3D::new(x2, 0f32, z2)
+                },
+                u1, v1,
+                u2, v1,
+                u1, v2,
+                u2, v2
+            );
+
+            // Bottom, facing -y
+            ret.add_face_uv(
+                Quad {
+                    tl: Vector3D::new(x2, 0f32, z1),
+                    tr: Vector3D::new(x1, 0f32, z1),
+                    bl: Vector3D::new(x2, 0f32, z2),
+                    br: Vector3D::new(x1, 0f32, z2)
+                },
+                1f32 - u2, v1,
+                1f32 - u1, v1,
+                1f32 - u2, v2,
+                1f32 - u1, v2
+            );
+        }
+    }
+
+    ret
+}
@@ -1,13 +1,22 @@
 use crate::math::quad::Quad;
 use crate::math::vector::Vector3D;
+use crate::math::VectorLike;
 use crate::model::Model;
 
+// Golden ratio, which the dodecahedron and icosahedron are built from
+const PHI: f32 = 1.618034f32;
+
+// Every solid is scaled to the cube's circumradius, so they all appear the same size
+const CIRCUMRADIUS: f32 = 1.7320508f32;
+
+// Faces are wound so that Quad::get_normal points out of the solid
+
 const CUBE_FACES: [Quad; 6] = [
     Quad {
-        tl: Vector3D { x: -1.0, y: 1.0, z: 1.0 },
-        tr: Vector3D { x: 1.0, y: 1.0, z: 1.0 },
-        bl: Vector3D { x: -1.0, y: -1.0, z: 1.0 },
-        br: Vector3D { x: 1.0, y: -1.0, z: 1.0 },
+        tl: Vector3D { x: 1.0, y: 1.0, z: 1.0 },
+        tr: Vector3D { x: -1.0, y: 1.0, z: 1.0 },
+        bl: Vector3D { x: 1.0, y: -1.0, z: 1.0 },
+        br: Vector3D { x: -1.0, y: -1.0, z: 1.0 },
     },
     Quad {
         tl: Vector3D { x: -1.0, y: 1.0, z: -1.0 },
@@ -41,12 +50,225 @@ const CUBE_FACES: [Quad; 6] = [
     }
 ];
 
-pub fn cube() -> Model {
+// Column and row of the cell for each face in CUBE_FACES, then the number of columns and rows
+type CubeCells = ([(u32, u32); 6], u32, u32);
+
+// Where each face of the cube takes its part of the texture from. Faces are named as seen from the camera before
+// the cube spins, and all of them are upright, with the top and bottom turned to meet the front.
+#[derive(Copy, Clone, PartialEq)]
+pub enum CubeLayout {
+    Same, // the whole texture on every face
+    Cross, // 4x3 horizontal cross, with left, front, right and back across the middle, top above and bottom below the front
+    Strip3x2, // left, front and right over back, top and bottom
+    Strip6x1 // left, front, right, back, top and bottom
+}
+
+impl CubeLayout {
+
+    fn get_cells(&self) -> Option<CubeCells> {
+        return match self {
+            CubeLayout::Same => None,
+            CubeLayout::Cross => Some(([ (3, 1), (1, 1), (0, 1), (2, 1), (1, 0), (1, 2) ], 4u32, 3u32)),
+            CubeLayout::Strip3x2 => Some(([ (0, 1), (1, 0), (0, 0), (2, 0), (1, 1), (2, 1) ], 3u32, 2u32)),
+            CubeLayout::Strip6x1 => Some(([ (3, 0), (1, 0), (0, 0), (2, 0), (4, 0), (5, 0) ], 6u32, 1u32))
+        };
+    }
+
+}
+
+pub fn cube(layout: CubeLayout) -> Model {
     let mut model: Model = Model::expect(6usize);
+    let cells: Option<CubeCells> = layout.get_cells();
 
     for idx in 0 .. 6usize {
-        model.add_face(CUBE_FACES[idx].copy());
+        match cells {
+            Some((cells, cols, rows)) => {
+                let (col, row) = cells[idx];
+                let u1: f32 = (col as f32) / (cols as f32);
+                let u2: f32 = ((col + 1u32) as f32) / (cols as f32);
+                let v1: f32 = (row as f32) / (rows as f32);
+                let v2: f32 = ((row + 1u32) as f32) / (rows as f32);
+                model.add_face_uv(CUBE_FACES[idx].copy(), u1, v1, u2, v1, u1, v2, u2, v2);
+            },
+            None => model.add_face(CUBE_FACES[idx].copy())
+        }
+    }
+
+    model
+}
+
+// Four triangles, laid out on a 2x2 texture atlas
+pub fn tetrahedron() -> Model {
+    let vertices: Vec<Vector3D> = vec![
+        Vector3D::new(1f32, 1f32, 1f32),
+        Vector3D::new(1f32, -1f32, -1f32),
+        Vector3D::new(-1f32, 1f32, -1f32),
+        Vector3D::new(-1f32, -1f32, 1f32)
+    ];
+    return triangle_solid(&vertices, 2u32, 2u32);
+}
+
+// Eight triangles, laid out on a 4x2 texture atlas
+pub fn octahedron() -> Model {
+    let vertices: Vec<Vector3D> = vec![
+        Vector3D::new(1f32, 0f32, 0f32),
+        Vector3D::new(-1f32, 0f32, 0f32),
+        Vector3D::new(0f32, 1f32, 0f32),
+        Vector3D::new(0f32, -1f32, 0f32),
+        Vector3D::new(0f32, 0f32, 1f32),
+        Vector3D::new(0f32, 0f32, -1f32)
+    ];
+    return triangle_solid(&vertices, 4u32, 2u32);
+}
+
+// Twenty triangles, laid out on a 5x4 texture atlas
+pub fn icosahedron() -> Model {
+    return triangle_solid(&icosahedron_vertices(), 5u32, 4u32);
+}
+
+// Twelve pentagons, laid out on a 4x3 texture atlas
+pub fn dodecahedron() -> Model {
+    let mut vertices: Vec<Vector3D> = Vec::with_capacity(20usize);
+    for sx in [ -1f32, 1f32 ] {
+        for sy in [ -1f32, 1f32 ] {
+            for sz in [ -1f32, 1f32 ] {
+                vertices.push(Vector3D::new(sx, sy, sz));
+            }
+        }
+    }
+    for a in [ -1f32, 1f32 ] {
+        for b in [ -1f32, 1f32 ] {
+            vertices.push(Vector3D::new(0f32, a * PHI, b / PHI));
+            vertices.push(Vector3D::new(a * PHI, b / PHI, 0f32));
+            vertices.push(Vector3D::new(b / PHI, 0f32, a * PHI));
+        }
+    }
+
+    // Each face lies opposite a vertex of the dual icosahedron, and is made of the 5 vertices nearest to it
+    let mut model: Model = Model::expect(24usize);
+    for (idx, dir) in icosahedron_vertices().iter().enumerate() {
+        let mut nearest: Vec<&Vector3D> = vertices.iter().collect();
+        nearest.sort_unstable_by(| a, b | b.dot(dir).total_cmp(&a.dot(dir)));
+        let face: Vec<Vector3D> = nearest[0 .. 5usize].iter().map(| v | v.copy()).collect();
+        add_polygon(&mut model, face, idx as u32, 4u32, 3u32);
+    }
+    model
+}
+
+fn icosahedron_vertices() -> Vec<Vector3D> {
+    let mut ret: Vec<Vector3D> = Vec::with_capacity(12usize);
+    for a in [ -1f32, 1f32 ] {
+        for b in [ -1f32, 1f32 ] {
+            ret.push(Vector3D::new(0f32, a, b * PHI));
+            ret.push(Vector3D::new(a, b * PHI, 0f32));
+            ret.push(Vector3D::new(b * PHI, 0f32, a));
+        }
+    }
+    ret
+}
+
+// Builds a solid whose faces are all the triangles of vertices at the shortest distance from one another
+fn triangle_solid(vertices: &[Vector3D], cols: u32, rows: u32) -> Model {
+    let dist = | a: &Vector3D, b: &Vector3D | {
+        let mut d: Vector3D = a.copy();
+        d.subtract_vector(b);
+        d.norm()
+    };
+    let mut edge: f32 = f32::INFINITY;
+    for i in 0 .. vertices.len() {
+        for j in (i + 1usize) .. vertices.len() {
+            edge = edge.min(dist(&vertices[i], &vertices[j]));
+        }
     }
+    let is_edge = | a: &Vector3D, b: &Vector3D | (dist(a, b) - edge).abs() <= edge * 0.01f32;
 
+    let mut model: Model = Model::new();
+    let mut cell: u32 = 0u32;
+    for i in 0 .. vertices.len() {
+        for j in (i + 1usize) .. vertices.len() {
+            if !is_edge(&vertices[i], &vertices[j]) { continue; }
+            for k in (j + 1usize) .. vertices.len() {
+                if !is_edge(&vertices[i], &vertices[k]) || !is_edge(&vertices[j], &vertices[k]) { continue; }
+                let face: Vec<Vector3D> = vec![ vertices[i].copy(), vertices[j].copy(), vertices[k].copy() ];
+                add_polygon(&mut model, face, cell, cols, rows);
+                cell += 1u32;
+            }
+        }
+    }
     model
 }
+
+// Adds a convex face of a solid centered on the origin, as one quad per pair of vertices after the first
+// (triangles become quads with the last two corners shared). The face is projected onto its own plane to
+// fill the given cell of a cols by rows texture atlas, with its first vertex pointing up.
+fn add_polygon(model: &mut Model, mut vertices: Vec<Vector3D>, cell: u32, cols: u32, rows: u32) {
+    let mut center: Vector3D = Vector3D::zero();
+    for v in vertices.iter() {
+        center.add_vector(v);
+    }
+    center.divide_scalar(vertices.len() as f32);
+    let mut normal: Vector3D = center.copy();
+    normal.normalize();
+
+    // Sort the corners around the face, counter-clockwise when seen from outside
+    let mut up: Vector3D = vertices[0].copy();
+    up.subtract_vector(&center);
+    up.normalize();
+    let side: Vector3D = normal.cross(&up);
+    let angle = | v: &Vector3D | {
+        let mut d: Vector3D = v.copy();
+        d.subtract_vector(&center);
+        d.dot(&side).atan2(d.dot(&up))
+    };
+    vertices.sort_unstable_by(| a, b | angle(a).total_cmp(&angle(b)));
+
+    // Normalize the size of the solid
+    let mut radius: f32 = 0f32;
+    for v in vertices.iter() {
+        radius = radius.max(v.norm());
+    }
+    let scale: f32 = CIRCUMRADIUS / radius;
+
+    let mut face_radius: f32 = 0f32;
+    for v in vertices.iter() {
+        let mut d: Vector3D = v.copy();
+        d.subtract_vector(&center);
+        face_radius = face_radius.max(d.norm());
+    }
+    let cell_u: f32 = ((cell % cols) as f32) + 0.5f32;
+    let cell_v: f32 = ((cell / cols) as f32) + 0.5f32;
+    let uv = | v: &Vector3D | {
+        let mut d: Vector3D = v.copy();
+        d.subtract_vector(&center);
+        let x: f32 = d.dot(&side) / face_radius;
+        let y: f32 = d.dot(&up) / face_radius;
+        ((cell_u + x * 0.45f32) / (cols as f32), (cell_v - y * 0.45f32) / (rows as f32))
+    };
+
+    let scaled: Vec<Vector3D> = vertices.iter().map(| v | {
+        let mut ret: Vector3D = v.copy();
+        ret.multiply_scalar(scale);
+        ret
+    }).collect();
+    let uvs: Vec<(f32, f32)> = vertices.iter().map(uv).collect();
+
+    // Quads run 0, i, i + 1, i + 2 around the face, and the last one repeats its final corner if it runs out
+    let mut i: usize = 1usize;
+    while i + 1usize < scaled.len() {
+        let c: usize = (i + 2usize).min(scaled.len() - 1usize);
+        let quad: Quad = Quad {
+            tl: scaled[0].copy(),
+            tr: scaled[i].copy(),
+            br: scaled[i + 1usize].copy(),
+            bl: scaled[c].copy()
+        };
+        model.add_face_uv(
+            quad,
+            uvs[0].0, uvs[0].1,
+            uvs[i].0, uvs[i].1,
+            uvs[c].0, uvs[c].1,
+            uvs[i + 1usize].0, uvs[i + 1usize].1
+        );
+        i += 2usize;
+    }
+}
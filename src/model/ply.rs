@@ -0,0 +1,583 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::str::SplitAsciiWhitespace;
+use crate::display::canvas::RGBA8;
+use crate::math::vector::Vector3D;
+use crate::model::{FaceCorner, Model, ModelFileError};
+use crate::model::material::UNTEXTURED_COLOR;
+
+#[derive(Copy, Clone, PartialEq)]
+enum PlyFormat {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum PlyType {
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Float32,
+    Float64
+}
+
+impl PlyType {
+
+    fn parse(name: &str) -> Option<PlyType> {
+        return match name {
+            "char" | "int8" => Some(PlyType::Int8),
+            "uchar" | "uint8" => Some(PlyType::UInt8),
+            "short" | "int16" => Some(PlyType::Int16),
+            "ushort" | "uint16" => Some(PlyType::UInt16),
+            "int" | "int32" => Some(PlyType::Int32),
+            "uint" | "uint32" => Some(PlyType::UInt32),
+            "float" | "float32" => Some(PlyType::Float32),
+            "double" | "float64" => Some(PlyType::Float64),
+            _ => None
+        };
+    }
+
+    fn get_size(&self) -> usize {
+        return match self {
+            PlyType::Int8 | PlyType::UInt8 => 1usize,
+            PlyType::Int16 | PlyType::UInt16 => 2usize,
+            PlyType::Int32 | PlyType::UInt32 | PlyType::Float32 => 4usize,
+            PlyType::Float64 => 8usize
+        };
+    }
+
+    // Scales a color channel of this type to 0 - 255, as floats run from 0 to 1 and integers run up to their largest
+    // value, with negative values left black
+    fn get_color_channel(&self, value: f64) -> u8 {
+        let max: f64 = match self {
+            PlyType::Int8 => i8::MAX as f64,
+            PlyType::UInt8 => u8::MAX as f64,
+            PlyType::Int16 => i16::MAX as f64,
+            PlyType::UInt16 => u16::MAX as f64,
+            PlyType::Int32 => i32::MAX as f64,
+            PlyType::UInt32 => u32::MAX as f64,
+            PlyType::Float32 | PlyType::Float64 => 1f64
+        };
+        return (value / max * 255f64).round().clamp(0f64, 255f64) as u8;
+    }
+
+}
+
+enum PlyProperty {
+    Scalar(String, PlyType),
+    List(String, PlyType, PlyType) // count type, then item type
+}
+
+struct PlyElement {
+    name: String,
+    count: usize,
+    properties: Vec<PlyProperty>
+}
+
+// Reads values one at a time from the body of the file, in whichever format it is in
+struct PlyReader<'a> {
+    format: PlyFormat,
+    data: &'a [u8],
+    pos: usize,
+    tokens: Option<SplitAsciiWhitespace<'a>>
+}
+
+impl PlyReader<'_> {
+
+    fn read(&mut self, ty: PlyType) -> Result<f64, ModelFileError> {
+        if let Some(tokens) = self.tokens.as_mut() {
+            return tokens.next()
+                .and_then(| t | t.parse::<f64>().ok())
+                .ok_or_else(|| ModelFileError::new("Invalid or missing value"));
+        }
+
+        let size: usize = ty.get_size();
+        let bytes: &[u8] = self.data.get(self.pos .. self.pos + size).ok_or_else(|| ModelFileError::new("Unexpected end of file"))?;
+        self.pos += size;
+        let mut b: [u8; 8] = [ 0u8; 8 ];
+        b[.. size].copy_from_slice(bytes);
+        if self.format == PlyFormat::BinaryBigEndian {
+            b[.. size].reverse();
+        }
+        return Ok(match ty {
+            PlyType::Int8 => (b[0] as i8) as f64,
+            PlyType::UInt8 => b[0] as f64,
+            PlyType::Int16 => i16::from_le_bytes([ b[0], b[1] ]) as f64,
+            PlyType::UInt16 => u16::from_le_bytes([ b[0], b[1] ]) as f64,
+            PlyType::Int32 => i32::from_le_bytes([ b[0], b[1], b[2], b[3] ]) as f64,
+            PlyType::UInt32 => u32::from_le_bytes([ b[0], b[1], b[2], b[3] ]) as f64,
+            PlyType::Float32 => f32::from_le_bytes([ b[0], b[1], b[2], b[3] ]) as f64,
+            PlyType::Float64 => f64::from_le_bytes(b)
+        });
+    }
+
+}
+
+// Loads an ASCII or binary PLY file, keeping the vertex colors and normals when it has them. Faces with four corners
+// stay as quads, and larger ones are split into fans of triangles. Without colors, the model is shown in a flat color.
+// It is centered on the origin and scaled to fit in the same 2 unit box as the cube.
+pub fn load_ply<P: AsRef<Path>>(path: P) -> Result<Model, ModelFileError> {
+    let bytes: Vec<u8> = fs::read(path).map_err(| err | ModelFileError::new(&format!("{}", err)))?;
+    if !bytes.starts_with(b"ply") {
+        return Err(ModelFileError::new("Not a PLY file"));
+    }
+
+    let marker: &[u8] = b"end_header";
+    let header_end: usize = bytes.windows(marker.len()).position(| w | w == marker)
+        .ok_or_else(|| ModelFileError::new("No end to the PLY header"))?;
+    let mut body: usize = header_end + marker.len();
+    if bytes.get(body) == Some(&b'\r') { body += 1usize; }
+    if bytes.get(body) == Some(&b'\n') { body += 1usize; }
+
+    let mut format: Option<PlyFormat> = None;
+    let mut elements: Vec<PlyElement> = Vec::new();
+    for line in String::from_utf8_lossy(&bytes[.. header_end]).lines() {
+        let words: Vec<&str> = line.split_ascii_whitespace().collect();
+        match words.as_slice() {
+            [ "format", name, .. ] => format = Some(match *name {
+                "ascii" => PlyFormat::Ascii,
+                "binary_little_endian" => PlyFormat::BinaryLittleEndian,
+                "binary_big_endian" => PlyFormat::BinaryBigEndian,
+                _ => return Err(ModelFileError::new(&format!("Unknown PLY format ({})", name)))
+            }),
+            [ "element", name, count ] => elements.push(PlyElement {
+                name: name.to_string(),
+                count: count.parse::<usize>().map_err(| _ | ModelFileError::new("Invalid element count"))?,
+                properties: Vec::new()
+            }),
+            [ "property", "list", count, item, name ] => {
+                let count: PlyType = PlyType::parse(count).ok_or_else(|| ModelFileError::new("Unknown property type"))?;
+                let item: PlyType = PlyType::parse(item).ok_or_else(|| ModelFileError::new("Unknown property type"))?;
+                let element: &mut PlyElement = elements.last_mut().ok_or_else(|| ModelFileError::new("Property outside of an element"))?;
+                element.properties.push(PlyProperty::List(name.to_string(), count, item));
+            },
+            [ "property", ty, name ] => {
+                let ty: PlyType = PlyType::parse(ty).ok_or_else(|| ModelFileError::new("Unknown property type"))?;
+                let element: &mut PlyElement = elements.last_mut().ok_or_else(|| ModelFileError::new("Property outside of an element"))?;
+                element.properties.push(PlyProperty::Scalar(name.to_string(), ty));
+            },
+            _ => { }
+        }
+    }
+
+    let format: PlyFormat = format.ok_or_else(|| ModelFileError::new("No PLY format given"))?;
+    let mut reader: PlyReader = PlyReader { format, data: &bytes[body ..], pos: 0usize, tokens: None };
+    if format == PlyFormat::Ascii {
+        let text: &str = std::str::from_utf8(&bytes[body ..]).map_err(| _ | ModelFileError::new("Invalid text in ASCII PLY"))?;
+        reader.tokens = Some(text.split_ascii_whitespace());
+    }
+
+    let mut positions: Vec<Vector3D> = Vec::new();
+    let mut normals: Vec<Vector3D> = Vec::new();
+    let mut colors: Vec<RGBA8> = Vec::new();
+    let mut faces: Vec<Vec<usize>> = Vec::new();
+    for element in elements.iter() {
+        let find = | names: &[&str] | element.properties.iter().position(| p | {
+            matches!(p, PlyProperty::Scalar(name, _) if names.contains(&name.as_str()))
+        });
+        let position: [Option<usize>; 3] = [ find(&[ "x" ]), find(&[ "y" ]), find(&[ "z" ]) ];
+        let normal: [Option<usize>; 3] = [ find(&[ "nx" ]), find(&[ "ny" ]), find(&[ "nz" ]) ];
+        let color: [Option<usize>; 4] = [
+            find(&[ "red", "diffuse_red", "r" ]),
+            find(&[ "green", "diffuse_green", "g" ]),
+            find(&[ "blue", "diffuse_blue", "b" ]),
+            find(&[ "alpha", "diffuse_alpha", "a" ])
+        ];
+        let indices: Option<usize> = element.properties.iter().position(| p | {
+            matches!(p, PlyProperty::List(name, _, _) if name == "vertex_indices" || name == "vertex_index")
+        });
+
+        let mut values: Vec<f64> = vec![0f64; element.properties.len()];
+        let mut list: Vec<usize> = Vec::new();
+        for _ in 0 .. element.count {
+            for (i, property) in element.properties.iter().enumerate() {
+                match property {
+                    PlyProperty::Scalar(_, ty) => values[i] = reader.read(*ty)?,
+                    PlyProperty::List(_, count_ty, item_ty) => {
+                        let count: usize = reader.read(*count_ty)?.max(0f64) as usize;
+                        let keep: bool = indices == Some(i);
+                        if keep { list.clear(); }
+                        for _ in 0 .. count {
+                            let item: f64 = reader.read(*item_ty)?;
+                            if keep { list.push(item.max(0f64) as usize); }
+                        }
+                    }
+                }
+            }
+
+            if element.name == "vertex" {
+                let get = | idx: Option<usize> | idx.map_or(0f64, | i | values[i]) as f32;
+                // Flipping z mirrors the model, which the reversed winding below makes up for
+                positions.push(Vector3D::new(get(position[0]), get(position[1]), -get(position[2])));
+                if normal.iter().all(| n | n.is_some()) {
+                    normals.push(Vector3D::new(get(normal[0]), get(normal[1]), -get(normal[2])));
+                }
+                if color[0 .. 3usize].iter().all(| c | c.is_some()) {
+                    let channel = | idx: Option<usize>, default: u8 | match (idx, idx.map(| i | &element.properties[i])) {
+                        (Some(i), Some(PlyProperty::Scalar(_, ty))) => ty.get_color_channel(values[i]),
+                        _ => default
+                    };
+                    colors.push(RGBA8 { r: channel(color[0], 0u8), g: channel(color[1], 0u8), b: channel(color[2], 0u8), a: channel(color[3], 255u8) });
+                }
+            } else if element.name == "face" && indices.is_some() {
+                faces.push(list.iter().rev().copied().collect());
+            }
+        }
+    }
+
+    if faces.is_empty() {
+        return Err(ModelFileError::new("No faces in file"));
+    }
+    if let Some(bad) = faces.iter().flatten().find(| i | **i >= positions.len()) {
+        return Err(ModelFileError::new(&format!("Face uses missing vertex {}", bad)));
+    }
+
+    // Faces share the file's vertices, and every face spans the whole texture
+    let mut ret: Model = Model::expect(faces.len());
+    let vertices: Vec<usize> = positions.into_iter().map(| p | ret.add_vertex(p)).collect();
+    let normals: Vec<usize> = normals.into_iter().map(| n | ret.add_normal(n)).collect();
+    let uvs: [usize; 4] = [ ret.add_uv(0f32, 0f32), ret.add_uv(1f32, 0f32), ret.add_uv(0f32, 1f32), ret.add_uv(1f32, 1f32) ];
+    let mut add = | corners: [usize; 4] | {
+        // Corners run around the face, while quads keep theirs as tl, tr, bl, br
+        let order: [usize; 4] = [ corners[0], corners[1], corners[3], corners[2] ];
+        ret.add_indexed_face(order.map(| i | vertices[i]), uvs);
+        let idx: usize = ret.get_face_count() - 1usize;
+        if !normals.is_empty() {
+            ret.set_face_normal_indices(idx, order.map(| i | normals[i]));
+        }
+        if !colors.is_empty() {
+            ret.set_face_colors(idx, order.map(| i | colors[i]));
+        }
+    };
+    for face in faces.iter() {
+        match face.len() {
+            0usize ..= 2usize => { },
+            4usize => add([ face[0], face[1], face[2], face[3] ]),
+            n => {
+                for i in 1usize .. n - 1usize {
+                    add([ face[0], face[i], face[i + 1usize], face[i + 1usize] ]);
+                }
+            }
+        }
+    }
+
+    ret.center_and_fit(2f32);
+    ret.material.set_color(if colors.is_empty() { &UNTEXTURED_COLOR } else { &RGBA8 { r: 255u8, g: 255u8, b: 255u8, a: 255u8 } });
+    Ok(ret)
+}
+
+// Writes models together as one binary PLY file. Corners that share every value are written as one vertex, with UVs
+// as s and t, normals when every face has them and colors when any face does. PLY is right-handed with y up, so z is
+// flipped and the winding reversed to keep the models looking as they do in blot. Each model's transform is applied if
+// bake is set.
+pub fn save_ply<P: AsRef<Path>>(models: &[&Model], path: P, bake: bool) -> Result<(), ModelFileError> {
+    let polygons: Vec<Vec<FaceCorner>> = models.iter()
+        .flat_map(| m | (0 .. m.get_face_count()).map(| idx | m.get_face_polygon(idx, bake)))
+        .filter(| p | p.len() >= 3usize)
+        .collect();
+    let has_normals: bool = polygons.iter().flatten().all(| c | c.normal.is_some());
+    let has_colors: bool = polygons.iter().flatten().any(| c | c.color.is_some());
+
+    let mut vertices: Vec<u8> = Vec::new();
+    let mut faces: Vec<u8> = Vec::new();
+    let mut indices: HashMap<Vec<u32>, u32> = HashMap::new();
+    for polygon in polygons.iter() {
+        faces.push(polygon.len() as u8);
+        for corner in polygon.iter().rev() {
+            let p: &Vector3D = &corner.position;
+            // PLY measures t up from the bottom of the texture
+            let mut values: Vec<f32> = vec![ p.x, p.y, -p.z, corner.uv.0, 1f32 - corner.uv.1 ];
+            if has_normals {
+                let n: Vector3D = corner.normal.as_ref().map_or(Vector3D::zero(), | n | n.copy());
+                values.extend_from_slice(&[ n.x, n.y, -n.z ]);
+            }
+            let color: RGBA8 = corner.color.unwrap_or(RGBA8::white());
+            let mut key: Vec<u32> = values.iter().map(| v | v.to_bits()).collect();
+            if has_colors {
+                key.push(u32::from_le_bytes([ color.r, color.g, color.b, color.a ]));
+            }
+
+            let next: u32 = indices.len() as u32;
+            let idx: u32 = *indices.entry(key).or_insert_with(|| {
+                for v in values.iter() {
+                    vertices.extend_from_slice(&v.to_le_bytes());
+                }
+                if has_colors {
+                    vertices.extend_from_slice(&[ color.r, color.g, color.b, color.a ]);
+                }
+                next
+            });
+            faces.extend_from_slice(&idx.to_le_bytes());
+        }
+    }
+
+    let mut header: String = String::from("ply\nformat binary_little_endian 1.0\ncomment blot\n");
+    header.push_str(&format!("element vertex {}\n", indices.len()));
+    for name in [ "x", "y", "z", "s", "t" ] {
+        header.push_str(&format!("property float {}\n", name));
+    }
+    if has_normals {
+        header.push_str("property float nx\nproperty float ny\nproperty float nz\n");
+    }
+    if has_colors {
+        header.push_str("property uchar red\nproperty uchar green\nproperty uchar blue\nproperty uchar alpha\n");
+    }
+    header.push_str(&format!("element face {}\nproperty list uchar uint vertex_indices\nend_header\n", polygons.len()));
+
+    let mut bytes: Vec<u8> = header.into_bytes();
+    bytes.extend_from_slice(&vertices);
+    bytes.extend_from_slice(&faces);
+    fs::write(path, bytes).map_err(| err | ModelFileError::new(&format!("{}", err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::{tempdir, TempDir};
+    use crate::model::testing::{assert_convex_faces_point_outward, load_bytes};
+    use crate::display::canvas::{ColorSpace, RGBA8F};
+    use crate::math::VectorLike;
+    use crate::model::platonic::{cube, CubeLayout};
+    use crate::model::sphere::uv_sphere;
+
+    // A unit cube with a color per vertex and one quad per side, wound counter-clockwise seen from outside
+    const CUBE_VERTICES: [[f32; 3]; 8] = [
+        [ 0f32, 0f32, 0f32 ], [ 1f32, 0f32, 0f32 ], [ 1f32, 1f32, 0f32 ], [ 0f32, 1f32, 0f32 ],
+        [ 0f32, 0f32, 1f32 ], [ 1f32, 0f32, 1f32 ], [ 1f32, 1f32, 1f32 ], [ 0f32, 1f32, 1f32 ]
+    ];
+    const CUBE_FACES: [[u32; 4]; 6] = [
+        [ 0, 3, 2, 1 ], [ 4, 5, 6, 7 ], [ 0, 1, 5, 4 ], [ 2, 3, 7, 6 ], [ 0, 4, 7, 3 ], [ 1, 2, 6, 5 ]
+    ];
+    const CUBE_HEADER: &str = "element vertex 8
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 6
+property list uchar int vertex_indices
+end_header
+";
+
+    fn cube_ascii() -> Vec<u8> {
+        let mut ret: String = format!("ply\nformat ascii 1.0\n{}", CUBE_HEADER);
+        for (i, v) in CUBE_VERTICES.iter().enumerate() {
+            ret.push_str(&format!("{} {} {} {} 0 0\n", v[0], v[1], v[2], i * 30usize));
+        }
+        for f in CUBE_FACES.iter() {
+            ret.push_str(&format!("4 {} {} {} {}\n", f[0], f[1], f[2], f[3]));
+        }
+        ret.into_bytes()
+    }
+
+    fn cube_binary(big_endian: bool) -> Vec<u8> {
+        let name: &str = if big_endian { "binary_big_endian" } else { "binary_little_endian" };
+        let mut ret: Vec<u8> = format!("ply\nformat {} 1.0\n{}", name, CUBE_HEADER).into_bytes();
+        for (i, v) in CUBE_VERTICES.iter().enumerate() {
+            for c in v {
+                ret.extend_from_slice(&if big_endian { c.to_be_bytes() } else { c.to_le_bytes() });
+            }
+            ret.extend_from_slice(&[ (i * 30usize) as u8, 0u8, 0u8 ]);
+        }
+        for f in CUBE_FACES.iter() {
+            ret.push(4u8);
+            for i in f {
+                ret.extend_from_slice(&if big_endian { i.to_be_bytes() } else { i.to_le_bytes() });
+            }
+        }
+        ret
+    }
+
+    fn corner_positions(model: &Model) -> Vec<[u32; 3]> {
+        (0 .. model.get_face_count())
+            .flat_map(| idx | model.get_face_polygon(idx, false))
+            .map(| c | [ c.position.x, c.position.y, c.position.z ].map(| v | v.to_bits()))
+            .collect()
+    }
+
+    #[test]
+    fn ascii_and_binary_formats_load_the_same() {
+        let ascii: Model = load_bytes("ply", &cube_ascii(), load_ply).unwrap();
+        let little: Model = load_bytes("ply", &cube_binary(false), load_ply).unwrap();
+        let big: Model = load_bytes("ply", &cube_binary(true), load_ply).unwrap();
+        assert_eq!(ascii.get_face_count(), 6usize);
+        assert_eq!(corner_positions(&ascii), corner_positions(&little));
+        assert_eq!(corner_positions(&ascii), corner_positions(&big));
+        for model in [ &ascii, &little, &big ] {
+            assert_convex_faces_point_outward("cube", model);
+            // Each corner keeps the color of the file vertex it came from, found again by undoing the fit and z flip
+            for corner in (0 .. 6usize).flat_map(| idx | model.get_face_polygon(idx, false)) {
+                let p: &Vector3D = &corner.position;
+                let file: [f32; 3] = [ (p.x + 1f32) / 2f32, (p.y + 1f32) / 2f32, (1f32 - p.z) / 2f32 ];
+                let vertex: usize = CUBE_VERTICES.iter().position(| v | v == &file).unwrap();
+                assert_eq!(corner.color.unwrap().r as usize, vertex * 30usize);
+            }
+        }
+    }
+
+    #[test]
+    fn flips_z_so_the_front_faces_the_camera() {
+        let text: &str = "ply
+format ascii 1.0
+element vertex 3
+property float x
+property float y
+property float z
+element face 1
+property list uchar int vertex_indices
+end_header
+0 0 0
+1 0 0
+0 1 0
+3 0 1 2
+";
+        let model: Model = load_bytes("ply", text.as_bytes(), load_ply).unwrap();
+        assert_eq!(model.get_face_count(), 1usize);
+        // Seen from +z in the file, which is the side of the model the camera looks at
+        assert!(model.get_face(0usize).get_normal().z < -0.99f32);
+    }
+
+    #[test]
+    fn splits_large_faces_into_fans_and_skips_other_lists() {
+        let text: &str = "ply
+format ascii 1.0
+element vertex 5
+property float x
+property float y
+property float z
+element face 2
+property list uchar float texcoord
+property list uchar int vertex_indices
+end_header
+0 0 0
+1 0 0
+1.5 1 0
+0.5 1.5 0
+-0.5 1 0
+2 0.5 0.5 5 0 1 2 3 4
+0 3 0 1 2
+";
+        let model: Model = load_bytes("ply", text.as_bytes(), load_ply).unwrap();
+        assert_eq!(model.get_face_count(), 4usize);
+        for idx in 0 .. model.get_face_count() {
+            assert!(model.get_face(idx).get_normal().z < -0.99f32);
+        }
+    }
+
+    #[test]
+    fn scales_float_and_short_colors() {
+        let text: &str = "ply
+format ascii 1.0
+element vertex 3
+property float x
+property float y
+property float z
+property float red
+property float green
+property ushort blue
+property ushort alpha
+element face 1
+property list uchar int vertex_indices
+end_header
+0 0 0 1 0.5 65535 65535
+1 0 0 1 0.5 65535 65535
+0 1 0 1 0.5 65535 32768
+3 0 1 2
+";
+        let model: Model = load_bytes("ply", text.as_bytes(), load_ply).unwrap();
+        let corners: Vec<FaceCorner> = model.get_face_polygon(0usize, false);
+        let colors: Vec<[u8; 4]> = corners.iter().map(| c | c.color.unwrap()).map(| c | [ c.r, c.g, c.b, c.a ]).collect();
+        assert!(colors.contains(&[ 255u8, 128u8, 255u8, 255u8 ]));
+        assert!(colors.contains(&[ 255u8, 128u8, 255u8, 128u8 ]));
+    }
+
+    #[test]
+    fn scales_signed_and_32_bit_colors_by_their_range() {
+        let text: &str = "ply
+format ascii 1.0
+element vertex 3
+property float x
+property float y
+property float z
+property char red
+property short green
+property uint blue
+property int alpha
+element face 1
+property list uchar int vertex_indices
+end_header
+0 0 0 127 32767 4294967295 2147483647
+1 0 0 -5 16384 2147483648 1073741824
+0 1 0 64 -1 0 0
+3 0 1 2
+";
+        let model: Model = load_bytes("ply", text.as_bytes(), load_ply).unwrap();
+        let corners: Vec<FaceCorner> = model.get_face_polygon(0usize, false);
+        let colors: Vec<[u8; 4]> = corners.iter().map(| c | c.color.unwrap()).map(| c | [ c.r, c.g, c.b, c.a ]).collect();
+        assert!(colors.contains(&[ 255u8, 255u8, 255u8, 255u8 ]));
+        assert!(colors.contains(&[ 0u8, 128u8, 128u8, 128u8 ]));
+        assert!(colors.contains(&[ 129u8, 0u8, 0u8, 0u8 ]));
+    }
+
+    #[test]
+    fn rejects_bad_files() {
+        assert!(load_bytes("ply", b"not a ply", load_ply).is_err());
+        assert!(load_bytes("ply", b"ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\n", load_ply).is_err());
+        let missing: &str = "ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\nelement face 1\nproperty list uchar int vertex_indices\nend_header\n0\n3 0 1 2\n";
+        assert!(load_bytes("ply", missing.as_bytes(), load_ply).is_err());
+        let mut truncated: Vec<u8> = cube_binary(false);
+        truncated.truncate(truncated.len() - 6usize);
+        assert!(load_bytes("ply", &truncated, load_ply).is_err());
+    }
+
+    #[test]
+    fn round_trips_through_save_ply() {
+        let dir: TempDir = tempdir().unwrap();
+        let path = dir.path().join("cube.ply");
+        let original: Model = cube(CubeLayout::Same);
+        save_ply(&[ &original ], &path, false).unwrap();
+        let loaded: Model = load_ply(&path).unwrap();
+        assert_eq!(loaded.get_face_count(), original.get_face_count());
+        for idx in 0 .. original.get_face_count() {
+            let a: Vec<FaceCorner> = original.get_face_polygon(idx, false);
+            let b: Vec<FaceCorner> = loaded.get_face_polygon(idx, false);
+            assert_eq!(a.len(), b.len());
+            for (ca, cb) in a.iter().zip(b.iter()) {
+                assert!(ca.position.fuzzy_equals(&cb.position), "face {} moved", idx);
+            }
+        }
+        assert_convex_faces_point_outward("saved cube", &loaded);
+    }
+
+    #[test]
+    fn round_trips_normals_and_colors() {
+        let dir: TempDir = tempdir().unwrap();
+        let path = dir.path().join("sphere.ply");
+        let mut original: Model = uv_sphere(8u16, 4u16);
+        let red: RGBA8 = RGBA8 { r: 200u8, g: 10u8, b: 20u8, a: 255u8 };
+        original.set_face_colors(0usize, [ red; 4 ]);
+        save_ply(&[ &original ], &path, false).unwrap();
+        let loaded: Model = load_ply(&path).unwrap();
+        assert_eq!(loaded.get_face_count(), original.get_face_count());
+        for idx in 0 .. original.get_face_count() {
+            let a: Vec<FaceCorner> = original.get_face_polygon(idx, false);
+            let b: Vec<FaceCorner> = loaded.get_face_polygon(idx, false);
+            assert_eq!(a.len(), b.len());
+            for ca in a.iter() {
+                let cb: &FaceCorner = b.iter().find(| c | c.position.fuzzy_equals(&ca.position)).unwrap();
+                assert!(ca.normal.as_ref().unwrap().dot(cb.normal.as_ref().unwrap()) > 0.999f32, "normal of face {} changed", idx);
+            }
+        }
+        let tint: RGBA8F = loaded.get_face_color(0usize, 0.5f32, 0.5f32, ColorSpace::Gamma).unwrap();
+        assert_eq!(tint.round().r, 200u8);
+        let plain: RGBA8F = loaded.get_face_color(1usize, 0.5f32, 0.5f32, ColorSpace::Gamma).unwrap();
+        assert_eq!(plain.round().g, 255u8);
+    }
+
+}
@@ -0,0 +1,158 @@
+use std::fs;
+use std::path::Path;
+use crate::math::vector::Vector3D;
+use crate::model::{Model, ModelFileError};
+use crate::model::material::UNTEXTURED_COLOR;
+
+// Loads an ASCII or binary STL file, as one triangle per facet. The normals stored in the file are ignored in favour of
+// the winding, which exporters get right far more often. The part is turned so that its z axis points up, centered on
+// the origin, and scaled to fit in the same 2 unit box as the cube.
+pub fn load_stl<P: AsRef<Path>>(path: P) -> Result<Model, ModelFileError> {
+    let bytes: Vec<u8> = fs::read(path).map_err(| err | ModelFileError::new(&format!("{}", err)))?;
+
+    // Binary files can start with "solid" too, so their length is the more reliable tell
+    let binary_count: Option<usize> = bytes.get(80usize .. 84usize).map(| b | u32::from_le_bytes([ b[0], b[1], b[2], b[3] ]) as usize);
+    let triangles: Vec<[Vector3D; 3]> = match binary_count {
+        Some(count) if bytes.len() == 84usize + count * 50usize => read_binary(&bytes[84usize ..]),
+        _ if bytes.starts_with(b"solid") => read_ascii(&String::from_utf8_lossy(&bytes))?,
+        _ => return Err(ModelFileError::new("Not an STL file"))
+    };
+    if triangles.is_empty() {
+        return Err(ModelFileError::new("No facets in file"));
+    }
+
+    let mut ret: Model = Model::expect(triangles.len());
+    for [ a, b, c ] in triangles {
+        // Swapping y and z mirrors the part, which the reversed winding makes up for
+        ret.add_triangle(z_up(&a), z_up(&c), z_up(&b));
+    }
+    ret.center_and_fit(2f32);
+    ret.material.set_color(&UNTEXTURED_COLOR);
+    Ok(ret)
+}
+
+fn z_up(v: &Vector3D) -> Vector3D {
+    Vector3D::new(v.x, v.z, v.y)
+}
+
+// 50 bytes per facet: the normal, three vertices and an attribute count, all little endian
+fn read_binary(data: &[u8]) -> Vec<[Vector3D; 3]> {
+    let float = | b: &[u8], pos: usize | f32::from_le_bytes([ b[pos], b[pos + 1usize], b[pos + 2usize], b[pos + 3usize] ]);
+    let vertex = | b: &[u8], pos: usize | Vector3D::new(float(b, pos), float(b, pos + 4usize), float(b, pos + 8usize));
+    data.chunks_exact(50usize).map(| facet | [ vertex(facet, 12usize), vertex(facet, 24usize), vertex(facet, 36usize) ]).collect()
+}
+
+// Facets hold a loop of vertices, which is split into a fan in case it has more than three
+fn read_ascii(text: &str) -> Result<Vec<[Vector3D; 3]>, ModelFileError> {
+    let mut ret: Vec<[Vector3D; 3]> = Vec::new();
+    let mut corners: Vec<Vector3D> = Vec::with_capacity(3usize);
+    let mut tokens = text.split_ascii_whitespace();
+    while let Some(token) = tokens.next() {
+        if token.eq_ignore_ascii_case("vertex") {
+            let mut coord = || -> Result<f32, ModelFileError> {
+                tokens.next().and_then(| t | t.parse::<f32>().ok()).ok_or_else(|| ModelFileError::new("Invalid vertex"))
+            };
+            corners.push(Vector3D::new(coord()?, coord()?, coord()?));
+        } else if token.eq_ignore_ascii_case("endloop") {
+            for i in 1usize .. corners.len().saturating_sub(1usize) {
+                ret.push([ corners[0].copy(), corners[i].copy(), corners[i + 1usize].copy() ]);
+            }
+            corners.clear();
+        }
+    }
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::testing::load_bytes;
+
+    fn binary(header: &[u8], facets: &[[[f32; 3]; 3]], count: u32) -> Vec<u8> {
+        let mut ret: Vec<u8> = header.to_vec();
+        ret.resize(80usize, 0u8);
+        ret.extend_from_slice(&count.to_le_bytes());
+        for facet in facets {
+            ret.extend_from_slice(&[ 0u8; 12 ]);
+            for vertex in facet {
+                for c in vertex {
+                    ret.extend_from_slice(&c.to_le_bytes());
+                }
+            }
+            ret.extend_from_slice(&[ 0u8; 2 ]);
+        }
+        ret
+    }
+
+    const TETRAHEDRON: [[[f32; 3]; 3]; 4] = [
+        [ [ 0f32, 0f32, 0f32 ], [ 0f32, 1f32, 0f32 ], [ 1f32, 0f32, 0f32 ] ],
+        [ [ 0f32, 0f32, 0f32 ], [ 1f32, 0f32, 0f32 ], [ 0f32, 0f32, 1f32 ] ],
+        [ [ 0f32, 0f32, 0f32 ], [ 0f32, 0f32, 1f32 ], [ 0f32, 1f32, 0f32 ] ],
+        [ [ 1f32, 0f32, 0f32 ], [ 0f32, 1f32, 0f32 ], [ 0f32, 0f32, 1f32 ] ]
+    ];
+
+    #[test]
+    fn loads_binary() {
+        let model: Model = load_bytes("stl", &binary(b"binary", &TETRAHEDRON, 4u32), load_stl).unwrap();
+        assert_eq!(model.get_face_count(), 4usize);
+        // Facets repeat their corners, which are stored once
+        assert_eq!(model.get_vertex_count(), 4usize);
+    }
+
+    #[test]
+    fn loads_binary_with_solid_header() {
+        let model: Model = load_bytes("stl", &binary(b"solid exported as binary", &TETRAHEDRON, 4u32), load_stl).unwrap();
+        assert_eq!(model.get_face_count(), 4usize);
+    }
+
+    #[test]
+    fn rejects_truncated_binary() {
+        let mut bytes: Vec<u8> = binary(b"binary", &TETRAHEDRON, 4u32);
+        bytes.truncate(bytes.len() - 10usize);
+        assert!(load_bytes("stl", &bytes, load_stl).is_err());
+
+        let mut bytes: Vec<u8> = binary(b"solid exported as binary", &TETRAHEDRON, 4u32);
+        bytes.truncate(bytes.len() - 10usize);
+        assert!(load_bytes("stl", &bytes, load_stl).is_err());
+    }
+
+    #[test]
+    fn loads_ascii_and_splits_loops_into_fans() {
+        let text: &str = "solid part
+  facet normal 0 0 1
+    outer loop
+      vertex 0 0 0
+      vertex 1 0 0
+      vertex 1 1 0
+      vertex 0 1 0
+    endloop
+  endfacet
+  facet normal 0 0 -1
+    outer loop
+      vertex 0 0 -1
+      vertex 0 1 -1
+      vertex 1 0 -1
+    endloop
+  endfacet
+endsolid part
+";
+        let model: Model = load_bytes("stl", text.as_bytes(), load_stl).unwrap();
+        assert_eq!(model.get_face_count(), 3usize);
+        // The file is z up, which becomes y up, and the winding keeps the front of each facet facing out
+        assert!(model.get_face(0usize).get_normal().y > 0.99f32);
+        assert!(model.get_face(1usize).get_normal().y > 0.99f32);
+        assert!(model.get_face(2usize).get_normal().y < -0.99f32);
+    }
+
+    #[test]
+    fn rejects_truncated_ascii() {
+        assert!(load_bytes("stl", b"solid part\n facet normal 0 0 1\n outer loop\n vertex 0 0", load_stl).is_err());
+        assert!(load_bytes("stl", b"solid part\nendsolid part\n", load_stl).is_err());
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert!(load_bytes("stl", b"not a model", load_stl).is_err());
+    }
+
+}
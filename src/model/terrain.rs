@@ -0,0 +1,115 @@
+use crate::model::Model;
+use crate::display::canvas::Canvas;
+use crate::math::quad::Quad;
+use crate::math::vector::Vector3D;
+use crate::model::parametric::ParametricSurface;
+
+// Square tile of terrain, size across, raised by up to height where the heightmap is white. The texture is draped over
+// the top with north at the top of the image, and the tile is closed with walls down to a flat base below its lowest
+// point so that it reads as a solid block from any side. The tile is centered on the origin.
+pub fn terrain(map: &dyn Canvas, size: f32, height: f32, mut cols: u16, mut rows: u16) -> Model {
+    if cols < 1u16 { cols = 1u16; }
+    if rows < 1u16 { rows = 1u16; }
+
+    let base: f32 = -height / 2f32 - size * 0.05f32;
+    let point = | u: f32, v: f32 | Vector3D::new((u - 0.5f32) * size, (sample_height(map, u, v) - 0.5f32) * height, (0.5f32 - v) * size);
+
+    // Slopes from the heights either side of each vertex, one grid step apart
+    let du: f32 = 1f32 / (cols as f32);
+    let dv: f32 = 1f32 / (rows as f32);
+    let normal = | u: f32, v: f32 | {
+        let hu: f32 = (sample_height(map, u + du, v) - sample_height(map, u - du, v)) / (2f32 * du);
+        let hv: f32 = (sample_height(map, u, v + dv) - sample_height(map, u, v - dv)) / (2f32 * dv);
+        Vector3D::new(-height * hu, size, height * hv)
+    };
+
+    let mut ret: Model = Model::expect(2usize * (cols as usize) * (rows as usize) + 2usize * ((cols as usize) + (rows as usize)));
+    ParametricSurface::new(point, cols, rows).normals(normal).build_into(&mut ret);
+
+    let below = | p: &Vector3D | Vector3D::new(p.x, base, p.z);
+    let mut add_wall = | tl: Vector3D, tr: Vector3D, u_l: f32, v_l: f32, u_r: f32, v_r: f32 | {
+        let quad: Quad = Quad { bl: below(&tl), br: below(&tr), tl, tr };
+        ret.add_face_uv(quad, u_l, v_l, u_r, v_r, u_l, v_l, u_r, v_r);
+    };
+    // Steps are found as the surface finds them, so that the walls and base share its vertices exactly
+    let step_u = | i: u16 | (i as f32) / (cols as f32);
+    let step_v = | j: u16 | (j as f32) / (rows as f32);
+    for i in 0 .. cols {
+        let u1: f32 = step_u(i);
+        let u2: f32 = step_u(i + 1u16);
+        add_wall(point(u2, 0f32), point(u1, 0f32), u2, 0f32, u1, 0f32);
+        add_wall(point(u1, 1f32), point(u2, 1f32), u1, 1f32, u2, 1f32);
+    }
+    for j in 0 .. rows {
+        let v1: f32 = step_v(j);
+        let v2: f32 = step_v(j + 1u16);
+        add_wall(point(0f32, v1), point(0f32, v2), 0f32, v1, 0f32, v2);
+        add_wall(point(1f32, v2), point(1f32, v1), 1f32, v2, 1f32, v1);
+    }
+
+    // The base is split like the surface, since a single large quad would be sorted behind whole hills
+    for j in 0 .. rows {
+        let (v1, v2) = (step_v(j), step_v(j + 1u16));
+        let (z1, z2) = ((0.5f32 - v1) * size, (0.5f32 - v2) * size);
+        for i in 0 .. cols {
+            let (u1, u2) = (step_u(i), step_u(i + 1u16));
+            let (x1, x2) = ((u1 - 0.5f32) * size, (u2 - 0.5f32) * size);
+            ret.add_face_uv(
+                Quad {
+                    tl: Vector3D::new(x2, base, z1),
+                    tr: Vector3D::new(x1, base, z1),
+                    bl: Vector3D::new(x2, base, z2),
+                    br: Vector3D::new(x1, base, z2)
+                },
+                1f32 - u2, v1,
+                1f32 - u1, v1,
+                1f32 - u2, v2,
+                1f32 - u1, v2
+            );
+        }
+    }
+
+    ret
+}
+
+// Brightness of the heightmap from 0 to 1, interpolated between pixels and clamped at the edges.
+// Heights are data rather than color, so the values are used as stored, without sRGB decoding.
+fn sample_height(map: &dyn Canvas, u: f32, v: f32) -> f32 {
+    let w: u32 = map.get_width();
+    let h: u32 = map.get_height();
+    if w == 0u32 || h == 0u32 { return 0f32; }
+
+    let x: f32 = (u.clamp(0f32, 1f32) * ((w - 1u32) as f32)).max(0f32);
+    let y: f32 = (v.clamp(0f32, 1f32) * ((h - 1u32) as f32)).max(0f32);
+    let x0: u32 = x.floor() as u32;
+    let y0: u32 = y.floor() as u32;
+    let x1: u32 = (x0 + 1u32).min(w - 1u32);
+    let y1: u32 = (y0 + 1u32).min(h - 1u32);
+    let tx: f32 = x - (x0 as f32);
+    let ty: f32 = y - (y0 as f32);
+
+    let value = | px: u32, py: u32 | {
+        let c = map.get_pixel(px, py);
+        ((c.r as f32) + (c.g as f32) + (c.b as f32)) / (3f32 * 255f32)
+    };
+    let top: f32 = value(x0, y0) + (value(x1, y0) - value(x0, y0)) * tx;
+    let bottom: f32 = value(x0, y1) + (value(x1, y1) - value(x0, y1)) * tx;
+    return top + (bottom - top) * ty;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{DynamicImage, Rgba, RgbaImage};
+    use crate::display::canvas::ImageCanvas;
+
+    #[test]
+    fn walls_and_base_share_the_surface_vertices() {
+        let map: RgbaImage = RgbaImage::from_fn(7u32, 5u32, | x, y | Rgba([ (x * 30u32 + y * 7u32) as u8, 0u8, 0u8, 255u8 ]));
+        let model: Model = terrain(&ImageCanvas::new(DynamicImage::ImageRgba8(map)), 3f32, 0.5f32, 4u16, 3u16);
+        // The surface and the base each have a grid of vertices, and the walls only join them up
+        assert_eq!(model.get_face_count(), 2usize * 4usize * 3usize + 2usize * (4usize + 3usize));
+        assert_eq!(model.get_vertex_count(), 2usize * 5usize * 4usize);
+    }
+
+}
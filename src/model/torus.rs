@@ -0,0 +1,24 @@
+use crate::model::Model;
+use core::f32::consts::PI;
+use crate::math::vector::Vector3D;
+use crate::model::parametric::ParametricSurface;
+
+// Ring around the y axis. The texture wraps once around the ring along u, and once around the tube along v
+// starting from its top and heading outward.
+pub fn torus(major: f32, minor: f32, mut segments: u16, mut sides: u16) -> Model {
+    if segments < 3u16 { segments = 3u16; }
+    if sides < 3u16 { sides = 3u16; }
+
+    let angles = | u: f32, v: f32 | (u * PI * 2f32, PI / 2f32 - v * PI * 2f32);
+    let point = move | u: f32, v: f32 | {
+        let (theta, phi) = angles(u, v);
+        let ring: f32 = major + minor * phi.cos();
+        Vector3D::new(ring * theta.cos(), minor * phi.sin(), ring * theta.sin())
+    };
+    let normal = move | u: f32, v: f32 | {
+        let (theta, phi) = angles(u, v);
+        Vector3D::new(phi.cos() * theta.cos(), phi.sin(), phi.cos() * theta.sin())
+    };
+
+    ParametricSurface::new(point, segments, sides).wrap(true, true).normals(normal).build()
+}
@@ -0,0 +1,280 @@
+use std::fs;
+use std::path::Path;
+use image::{DynamicImage, Rgba, RgbaImage};
+use crate::display::canvas::ImageCanvas;
+use crate::math::quad::Quad;
+use crate::math::vector::Vector3D;
+use crate::math::VectorLike;
+use crate::model::{Model, ModelFileError};
+
+// Palette entries are laid out on a 16x16 texture, with every face sampling the center of its color's texel
+const PALETTE_SIDE: u32 = 16u32;
+
+// Loads the first model in a MagicaVoxel .vox file. Faces between voxels are dropped, and neighbouring faces of the
+// same color are merged into larger quads. The model is turned so that MagicaVoxel's z axis points up, centered
+// on the origin, and scaled to fit in the same 2 unit box as the cube.
+pub fn load_vox<P: AsRef<Path>>(path: P) -> Result<Model, ModelFileError> {
+    let bytes: Vec<u8> = fs::read(path).map_err(| err | ModelFileError::new(&format!("{}", err)))?;
+    if bytes.len() < 8usize || &bytes[0 .. 4usize] != b"VOX " {
+        return Err(ModelFileError::new("Not a MagicaVoxel file"));
+    }
+
+    let read_u32 = | pos: usize | -> Result<u32, ModelFileError> {
+        bytes.get(pos .. pos + 4usize)
+            .map(| b | u32::from_le_bytes([ b[0], b[1], b[2], b[3] ]))
+            .ok_or_else(|| ModelFileError::new("Unexpected end of file"))
+    };
+
+    let mut size: Option<[usize; 3]> = None;
+    let mut voxels: Vec<u8> = Vec::new();
+    let mut palette: [[u8; 4]; 256] = default_palette();
+
+    // MAIN holds every other chunk as its children, so the chunks can be read as one flat list after its header
+    let mut pos: usize = 8usize;
+    while pos + 12usize <= bytes.len() {
+        let id: &[u8] = &bytes[pos .. pos + 4usize];
+        let content: usize = read_u32(pos + 4usize)? as usize;
+        let start: usize = pos + 12usize;
+        pos = if id == b"MAIN" { start } else { start + content };
+        if pos > bytes.len() {
+            return Err(ModelFileError::new("Unexpected end of file"));
+        }
+
+        match id {
+            b"SIZE" if size.is_none() => {
+                let dims: [usize; 3] = [ read_u32(start)? as usize, read_u32(start + 4usize)? as usize, read_u32(start + 8usize)? as usize ];
+                if dims.iter().any(| d | *d == 0usize || *d > 256usize) {
+                    return Err(ModelFileError::new("Invalid model size"));
+                }
+                size = Some(dims);
+            },
+            b"XYZI" if voxels.is_empty() => {
+                let dims: [usize; 3] = size.ok_or_else(|| ModelFileError::new("Voxels before model size"))?;
+                voxels = vec![0u8; dims[0] * dims[1] * dims[2]];
+                let count: usize = read_u32(start)? as usize;
+                let data: &[u8] = bytes.get(start + 4usize .. start + 4usize + count * 4usize)
+                    .ok_or_else(|| ModelFileError::new("Unexpected end of file"))?;
+                for voxel in data.chunks_exact(4usize) {
+                    let (x, y, z) = (voxel[0] as usize, voxel[1] as usize, voxel[2] as usize);
+                    if x < dims[0] && y < dims[1] && z < dims[2] {
+                        voxels[(z * dims[1] + y) * dims[0] + x] = voxel[3];
+                    }
+                }
+            },
+            b"RGBA" => {
+                let data: &[u8] = bytes.get(start .. start + 1024usize).ok_or_else(|| ModelFileError::new("Unexpected end of file"))?;
+                // Entry i holds the color of index i + 1
+                for (i, color) in data.chunks_exact(4usize).take(255usize).enumerate() {
+                    palette[i + 1usize] = [ color[0], color[1], color[2], color[3] ];
+                }
+            },
+            _ => { }
+        }
+    }
+
+    let size: [usize; 3] = size.ok_or_else(|| ModelFileError::new("No model in file"))?;
+    if voxels.is_empty() {
+        return Err(ModelFileError::new("No voxels in file"));
+    }
+
+    let mut ret: Model = mesh(&voxels, size);
+
+    let mut image: RgbaImage = RgbaImage::new(PALETTE_SIDE, PALETTE_SIDE);
+    for (i, color) in palette.iter().enumerate() {
+        image.put_pixel((i as u32) % PALETTE_SIDE, (i as u32) / PALETTE_SIDE, Rgba::<u8>::from(*color));
+    }
+    ret.material.texture = Some(ImageCanvas::new(DynamicImage::ImageRgba8(image)));
+    Ok(ret)
+}
+
+// Greedy meshing: each slice through the model along each axis gets a mask of the voxel faces that can be seen
+// from that side, and runs of the same color are grown into rectangles, first along one axis and then the other.
+// Empty space sealed inside the model can't be seen into, so only faces onto space reachable from outside are kept.
+fn mesh(voxels: &[u8], size: [usize; 3]) -> Model {
+    let index = | p: [i64; 3] | ((p[2] as usize) * size[1] + (p[1] as usize)) * size[0] + (p[0] as usize);
+    let inside = | p: [i64; 3] | (0 .. 3usize).all(| a | p[a] >= 0i64 && p[a] < (size[a] as i64));
+    let get = | p: [i64; 3] | -> u8 {
+        if !inside(p) { return 0u8; }
+        voxels[index(p)]
+    };
+
+    // Flood fill the empty space from every empty voxel on the boundary
+    let mut outside: Vec<bool> = vec![false; voxels.len()];
+    let mut open: Vec<[i64; 3]> = Vec::new();
+    for z in 0 .. size[2] as i64 {
+        for y in 0 .. size[1] as i64 {
+            for x in 0 .. size[0] as i64 {
+                let p: [i64; 3] = [ x, y, z ];
+                let edge: bool = (0 .. 3usize).any(| a | p[a] == 0i64 || p[a] == (size[a] as i64) - 1i64);
+                if edge && get(p) == 0u8 {
+                    outside[index(p)] = true;
+                    open.push(p);
+                }
+            }
+        }
+    }
+    while let Some(p) = open.pop() {
+        for a in 0 .. 3usize {
+            for d in [ -1i64, 1i64 ] {
+                let mut n: [i64; 3] = p;
+                n[a] += d;
+                if inside(n) && get(n) == 0u8 && !outside[index(n)] {
+                    outside[index(n)] = true;
+                    open.push(n);
+                }
+            }
+        }
+    }
+    let exposed = | p: [i64; 3] | !inside(p) || outside[index(p)];
+
+    let scale: f32 = 2f32 / (size.iter().copied().max().unwrap_or(1usize) as f32);
+    // MagicaVoxel looks down its y axis with z up, which is this renderer's z axis with y up
+    let to_world = | p: [usize; 3] | Vector3D::new(
+        ((p[0] as f32) - (size[0] as f32) / 2f32) * scale,
+        ((p[2] as f32) - (size[2] as f32) / 2f32) * scale,
+        ((p[1] as f32) - (size[1] as f32) / 2f32) * scale
+    );
+
+    let mut ret: Model = Model::new();
+    for axis in 0 .. 3usize {
+        let ua: usize = (axis + 1usize) % 3usize;
+        let va: usize = (axis + 2usize) % 3usize;
+        let mut mask: Vec<u8> = vec![0u8; size[ua] * size[va]];
+
+        for dir in [ -1i64, 1i64 ] {
+            for slice in 0 .. size[axis] {
+                for v in 0 .. size[va] {
+                    for u in 0 .. size[ua] {
+                        let mut p: [i64; 3] = [ 0i64; 3 ];
+                        p[axis] = slice as i64;
+                        p[ua] = u as i64;
+                        p[va] = v as i64;
+                        let color: u8 = get(p);
+                        p[axis] += dir;
+                        mask[v * size[ua] + u] = if color != 0u8 && exposed(p) { color } else { 0u8 };
+                    }
+                }
+
+                for v in 0 .. size[va] {
+                    let mut u: usize = 0usize;
+                    while u < size[ua] {
+                        let color: u8 = mask[v * size[ua] + u];
+                        if color == 0u8 {
+                            u += 1usize;
+                            continue;
+                        }
+
+                        let mut w: usize = 1usize;
+                        while u + w < size[ua] && mask[v * size[ua] + u + w] == color { w += 1usize; }
+                        let mut h: usize = 1usize;
+                        while v + h < size[va] && mask[(v + h) * size[ua] + u .. (v + h) * size[ua] + u + w].iter().all(| c | *c == color) {
+                            h += 1usize;
+                        }
+                        for row in v .. v + h {
+                            mask[row * size[ua] + u .. row * size[ua] + u + w].fill(0u8);
+                        }
+
+                        let corner = | du: usize, dv: usize | {
+                            let mut p: [usize; 3] = [ 0usize; 3 ];
+                            p[axis] = if dir > 0i64 { slice + 1usize } else { slice };
+                            p[ua] = u + du;
+                            p[va] = v + dv;
+                            to_world(p)
+                        };
+                        let mut normal: [usize; 3] = [ 0usize; 3 ];
+                        normal[axis] = 1usize;
+                        let mut outward: Vector3D = to_world(normal);
+                        outward.subtract_vector(&to_world([ 0usize; 3 ]));
+                        if dir < 0i64 { outward.negate(); }
+
+                        add_colored_face(&mut ret, Quad { tl: corner(0usize, h), tr: corner(w, h), bl: corner(0usize, 0usize), br: corner(w, 0usize) }, &outward, color);
+                        u += w;
+                    }
+                }
+            }
+        }
+    }
+    ret
+}
+
+// Adds a face showing a single palette color, wound so that its normal points along outward
+fn add_colored_face(model: &mut Model, mut quad: Quad, outward: &Vector3D, color: u8) {
+    if quad.get_normal().dot(outward) < 0f32 {
+        std::mem::swap(&mut quad.tl, &mut quad.tr);
+        std::mem::swap(&mut quad.bl, &mut quad.br);
+    }
+    let u: f32 = (((color as u32) % PALETTE_SIDE) as f32 + 0.5f32) / (PALETTE_SIDE as f32);
+    let v: f32 = (((color as u32) / PALETTE_SIDE) as f32 + 0.5f32) / (PALETTE_SIDE as f32);
+    model.add_face_uv(quad, u, v, u, v, u, v, u, v);
+}
+
+// MagicaVoxel's palette for files without one: a 6x6x6 color cube without black, then ramps of red, green, blue and gray
+fn default_palette() -> [[u8; 4]; 256] {
+    let mut ret: [[u8; 4]; 256] = [[ 0u8; 4 ]; 256];
+    let mut i: usize = 1usize;
+    for r in (0 .. 6u8).rev() {
+        for g in (0 .. 6u8).rev() {
+            for b in (0 .. 6u8).rev() {
+                if r == 0u8 && g == 0u8 && b == 0u8 { continue; }
+                ret[i] = [ r * 51u8, g * 51u8, b * 51u8, 255u8 ];
+                i += 1usize;
+            }
+        }
+    }
+    let ramp: [u8; 10] = [ 0xee, 0xdd, 0xbb, 0xaa, 0x88, 0x77, 0x55, 0x44, 0x22, 0x11 ];
+    for channels in [ [ true, false, false ], [ false, true, false ], [ false, false, true ], [ true, true, true ] ] {
+        for value in ramp {
+            let pick = | on: bool | if on { value } else { 0u8 };
+            ret[i] = [ pick(channels[0]), pick(channels[1]), pick(channels[2]), 255u8 ];
+            i += 1usize;
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_a_block_of_two_into_six_faces() {
+        let model: Model = mesh(&[ 1u8, 1u8 ], [ 2usize, 1usize, 1usize ]);
+        assert_eq!(model.get_face_count(), 6usize);
+        assert_eq!(model.get_vertex_count(), 8usize);
+    }
+
+    #[test]
+    fn keeps_colors_apart() {
+        let model: Model = mesh(&[ 1u8, 2u8 ], [ 2usize, 1usize, 1usize ]);
+        // The end caps stay whole, and the four long sides split at the color change
+        assert_eq!(model.get_face_count(), 10usize);
+    }
+
+    #[test]
+    fn hollow_shell_has_no_interior_faces() {
+        let mut voxels: Vec<u8> = vec![ 1u8; 27usize ];
+        voxels[13usize] = 0u8;
+        let model: Model = mesh(&voxels, [ 3usize, 3usize, 3usize ]);
+        assert_eq!(model.get_face_count(), 6usize);
+        // Every face lies on the outside of the 2 unit box
+        for idx in 0 .. model.get_face_count() {
+            let center: Vector3D = model.get_face(idx).get_center();
+            let extent: f32 = center.x.abs().max(center.y.abs()).max(center.z.abs());
+            assert!((extent - 1f32).abs() < 0.0001f32, "face {} is inside the shell", idx);
+        }
+    }
+
+    #[test]
+    fn open_cavity_is_meshed() {
+        // The middle of a 3x3x1 ring is open at top and bottom, so its inner walls are visible
+        let mut voxels: Vec<u8> = vec![ 1u8; 9usize ];
+        voxels[4usize] = 0u8;
+        let model: Model = mesh(&voxels, [ 3usize, 3usize, 1usize ]);
+        let inner: usize = (0 .. model.get_face_count()).filter(| idx | {
+            let center: Vector3D = model.get_face(*idx).get_center();
+            center.x.abs().max(center.z.abs()) < 0.5f32
+        }).count();
+        assert_eq!(inner, 4usize);
+    }
+
+}
@@ -0,0 +1,208 @@
+use std::f32::consts::PI;
+use crate::math::quaternion::Quaternion;
+use crate::math::transform::Transform;
+use crate::math::vector::Vector3D;
+use crate::math::VectorLike;
+use crate::model::Model;
+
+// A transform with an optional model and children placed relative to it, such as a moon about its planet
+pub struct SceneNode {
+    pub transform: Transform,
+    pub model: Option<Model>,
+    pub children: Vec<SceneNode>,
+    pub spin: Vector3D // whole turns about x, y and z over one loop of the animation, or none to keep the rotation
+}
+
+impl SceneNode {
+
+    pub fn new(model: Option<Model>) -> Self {
+        return Self {
+            transform: Transform::new(),
+            model,
+            children: Vec::new(),
+            spin: Vector3D::zero()
+        };
+    }
+
+    pub fn add_child(&mut self, child: SceneNode) -> &mut SceneNode {
+        self.children.push(child);
+        return self.children.last_mut().unwrap();
+    }
+
+    fn spin(&mut self, progress: f32) {
+        if self.spin.norm_sqr() > 0f32 {
+            let angle: f32 = progress * PI * 2f32;
+            self.transform.rotation = Quaternion::from_euler(angle * self.spin.x, angle * self.spin.y, angle * self.spin.z);
+        }
+        for child in self.children.iter_mut() {
+            child.spin(progress);
+        }
+    }
+
+    fn update(&mut self, parent: &Transform) {
+        let mut world: Transform = self.transform.copy();
+        parent.transform(&mut world);
+        if let Some(model) = self.model.as_mut() {
+            model.transform = world.copy();
+        }
+        for child in self.children.iter_mut() {
+            child.update(&world);
+        }
+    }
+
+    fn collect_models<'a>(&'a self, out: &mut Vec<&'a Model>) {
+        if let Some(model) = self.model.as_ref() {
+            out.push(model);
+        }
+        for child in self.children.iter() {
+            child.collect_models(out);
+        }
+    }
+
+    fn collect_models_mut<'a>(&'a mut self, out: &mut Vec<&'a mut Model>) {
+        if let Some(model) = self.model.as_mut() {
+            out.push(model);
+        }
+        for child in self.children.iter_mut() {
+            child.collect_models_mut(out);
+        }
+    }
+
+}
+
+// Nodes to be drawn together. A model's own transform is overwritten with its node's world transform on update, so
+// nodes should be moved instead.
+pub struct Scene {
+    pub nodes: Vec<SceneNode>
+}
+
+impl Scene {
+
+    pub fn new() -> Self {
+        return Self { nodes: Vec::new() };
+    }
+
+    pub fn add_node(&mut self, node: SceneNode) -> &mut SceneNode {
+        self.nodes.push(node);
+        return self.nodes.last_mut().unwrap();
+    }
+
+    // Turns every node that spins to where it is at progress, from 0 to 1, through the loop
+    pub fn spin(&mut self, progress: f32) {
+        for node in self.nodes.iter_mut() {
+            node.spin(progress);
+        }
+    }
+
+    // Composes the transforms down from each top level node, and gives every model its world transform
+    pub fn update(&mut self) {
+        let origin: Transform = Transform::new();
+        for node in self.nodes.iter_mut() {
+            node.update(&origin);
+        }
+    }
+
+    // Every model in the scene, parents before their children
+    pub fn get_models(&self) -> Vec<&Model> {
+        let mut ret: Vec<&Model> = Vec::new();
+        for node in self.nodes.iter() {
+            node.collect_models(&mut ret);
+        }
+        return ret;
+    }
+
+    pub fn get_models_mut(&mut self) -> Vec<&mut Model> {
+        let mut ret: Vec<&mut Model> = Vec::new();
+        for node in self.nodes.iter_mut() {
+            node.collect_models_mut(&mut ret);
+        }
+        return ret;
+    }
+
+    // Sphere around the top level nodes holding every model, as of the last update
+    pub fn get_bounds(&self) -> (Vector3D, f32) {
+        let mut center: Vector3D = Vector3D::zero();
+        for node in self.nodes.iter() {
+            center.add_vector(&node.transform.translation);
+        }
+        if !self.nodes.is_empty() {
+            center.multiply_scalar(1f32 / (self.nodes.len() as f32));
+        }
+
+        let mut radius: f32 = 0f32;
+        for model in self.get_models() {
+            let mut offset: Vector3D = model.transform.translation.copy();
+            offset.subtract_vector(&center);
+            radius = radius.max(offset.norm() + model.get_radius());
+        }
+        return (center, radius);
+    }
+
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::testing::assert_close;
+    use crate::model::platonic::{cube, CubeLayout};
+
+    // A cube with a moon hung two units out from a pivot that turns once about y
+    fn orbit_scene() -> Scene {
+        let mut scene: Scene = Scene::new();
+        let node: &mut SceneNode = scene.add_node(SceneNode::new(Some(cube(CubeLayout::Same))));
+        let mut moon: SceneNode = SceneNode::new(Some(cube(CubeLayout::Same)));
+        moon.transform.scale_scalar(0.5f32);
+        moon.transform.translate(&Vector3D::new(2f32, 0f32, 0f32));
+        let mut pivot: SceneNode = SceneNode::new(None);
+        pivot.spin = Vector3D::new(0f32, 1f32, 0f32);
+        pivot.add_child(moon);
+        node.add_child(pivot);
+        scene
+    }
+
+    #[test]
+    fn children_of_a_spinning_pivot_orbit_it() {
+        let mut scene: Scene = orbit_scene();
+        let mut seen: Vec<Vector3D> = Vec::new();
+        for progress in [ 0f32, 0.25f32, 0.5f32, 1f32 ] {
+            scene.spin(progress);
+            scene.update();
+            let models: Vec<&Model> = scene.get_models();
+            assert_eq!(models.len(), 2usize);
+            // The node without a spin stays put
+            assert_close(&models[0].transform.translation, &Vector3D::zero());
+            let moon: Vector3D = models[1].transform.translation.copy();
+            assert!((moon.norm() - 2f32).abs() < 1e-4);
+            assert!(moon.y.abs() < 1e-4);
+            seen.push(moon);
+        }
+        assert_close(&seen[0], &Vector3D::new(2f32, 0f32, 0f32));
+        assert!(seen[1].x.abs() < 1e-4);
+        assert_close(&seen[2], &Vector3D::new(-2f32, 0f32, 0f32));
+        // A whole turn comes back to the start, so the animation loops
+        assert_close(&seen[3], &seen[0]);
+    }
+
+    #[test]
+    fn bounds_reach_past_the_orbit() {
+        let mut scene: Scene = orbit_scene();
+        scene.update();
+        let (center, radius) = scene.get_bounds();
+        let cube_radius: f32 = 3f32.sqrt();
+        assert_close(&center, &Vector3D::zero());
+        assert!((radius - (2f32 + 0.5f32 * cube_radius)).abs() < 1e-4);
+
+        // Spinning keeps the moon as far out
+        scene.spin(0.3f32);
+        scene.update();
+        let (_, spun) = scene.get_bounds();
+        assert!((spun - radius).abs() < 1e-4);
+    }
+
+}